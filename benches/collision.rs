@@ -0,0 +1,92 @@
+use bevy::{ecs::entity::Entity, math::Vec2, sprite::collide_aabb::collide};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use monkey_fire::collision::SpatialHash;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+const WIDTH: f32 = 1280.0;
+const HEIGHT: f32 = 720.0;
+const SIZE: f32 = 35.0;
+
+struct Projectile {
+    entity: Entity,
+    center: Vec2,
+}
+
+fn projectiles(count: u32) -> Vec<Projectile> {
+    let mut rng = StdRng::seed_from_u64(42);
+    (0..count)
+        .map(|id| Projectile {
+            entity: Entity::from_raw(id),
+            center: Vec2::new(
+                rng.gen_range(-WIDTH / 2.0..WIDTH / 2.0),
+                rng.gen_range(-HEIGHT / 2.0..HEIGHT / 2.0),
+            ),
+        })
+        .collect()
+}
+
+fn hits(a: &Projectile, b: &Projectile) -> bool {
+    let size = Vec2::splat(SIZE);
+    collide(a.center.extend(0.0), size, b.center.extend(0.0), size).is_some()
+}
+
+fn brute_force(projectiles: &[Projectile]) -> usize {
+    let mut count = 0;
+    for a in projectiles {
+        for b in projectiles {
+            if a.entity != b.entity && hits(a, b) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn spatial_hashed(spatial_hash: &mut SpatialHash, projectiles: &[Projectile]) -> usize {
+    spatial_hash.clear();
+    for p in projectiles {
+        spatial_hash.insert(p.entity, p.center, Vec2::splat(SIZE));
+    }
+
+    let mut count = 0;
+    let mut candidates = Vec::new();
+    for a in projectiles {
+        spatial_hash.query(a.center, Vec2::splat(SIZE), &mut candidates);
+        for &b in candidates.iter() {
+            let b = &projectiles[b.id() as usize];
+            if a.entity != b.entity && hits(a, b) {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn collision_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision");
+    let mut spatial_hash = SpatialHash::new(128.0);
+
+    for count in [1000, 2000, 4000] {
+        let projectiles = projectiles(count);
+        assert_eq!(
+            brute_force(&projectiles),
+            spatial_hashed(&mut spatial_hash, &projectiles)
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("brute_force", count),
+            &projectiles,
+            |b, p| b.iter(|| brute_force(black_box(p))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("spatial_hash", count),
+            &projectiles,
+            |b, p| b.iter(|| spatial_hashed(&mut spatial_hash, black_box(p))),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, collision_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,273 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sprites::Clip;
+
+/// Plays every `Animator`, and despawns entities marked `DespawnOnFinish` when their
+/// animation ends.
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AnimationFinished>()
+            .add_system(animation_system.label(AnimationLabel))
+            .add_system(animation_despawn_system.after(AnimationLabel));
+    }
+}
+
+/// Label for the system that plays the animations, run after it to see their
+/// `AnimationFinished` events on the same frame.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct AnimationLabel;
+
+/// How a clip carries on after its last frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Playback {
+    /// Back to the first frame.
+    #[default]
+    Loop,
+    /// Stay on the last frame, or move on to the clip's `next` clip.
+    Once,
+    /// Back and forth between the first and last frames.
+    PingPong,
+}
+
+/// Sent when a `Playback::Once` clip gets past its last frame.
+#[derive(Debug)]
+pub struct AnimationFinished {
+    pub entity: Entity,
+    pub clip: String,
+}
+
+/// Despawn the entity once its animation finishes.
+#[derive(Component)]
+pub struct DespawnOnFinish;
+
+/// Plays the named clips of a sprite sheet on the entity's `TextureAtlasSprite`.
+#[derive(Component, Clone, Debug)]
+pub struct Animator {
+    clips: Arc<BTreeMap<String, Clip>>,
+    name: String,
+    clip: Clip,
+    /// Frame within the clip, counting from its first frame.
+    frame: usize,
+    backwards: bool,
+    finished: bool,
+    timer: Timer,
+}
+
+impl Animator {
+    pub fn new(clips: Arc<BTreeMap<String, Clip>>, name: &str) -> Self {
+        let mut animator = Self {
+            clips,
+            name: String::new(),
+            clip: Clip::default(),
+            frame: 0,
+            backwards: false,
+            finished: false,
+            timer: Timer::default(),
+        };
+        animator.restart(name);
+        animator
+    }
+
+    /// Switch to the named clip, unless it is already playing. Shows the first frame if the
+    /// sheet has no such clip.
+    pub fn play(&mut self, name: &str) {
+        if self.name != name {
+            self.restart(name);
+        }
+    }
+
+    /// Play the named clip from the start, even if it is already playing.
+    pub fn restart(&mut self, name: &str) {
+        self.name = name.to_string();
+        self.clip = self.clips.get(name).cloned().unwrap_or_default();
+        self.frame = 0;
+        self.backwards = false;
+        self.finished = false;
+        self.timer = Timer::from_seconds(self.clip.frame_duration, true);
+    }
+
+    pub fn has_clip(&self, name: &str) -> bool {
+        self.clips.contains_key(name)
+    }
+
+    /// Name of the clip playing.
+    pub fn clip(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether a `Playback::Once` clip is still going, so shouldn't be cut short.
+    pub fn playing_once(&self) -> bool {
+        self.clip.playback == Playback::Once && !self.finished
+    }
+
+    /// Whether a `Playback::Once` clip has ended on its last frame.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Sprite sheet index of the frame to show.
+    pub fn index(&self) -> usize {
+        self.clip.first + self.frame
+    }
+
+    /// Move the animation on by `delta`, returning the name of the clip if it finished.
+    pub fn tick(&mut self, delta: Duration) -> Option<String> {
+        if self.finished {
+            return None;
+        }
+
+        self.timer.tick(delta);
+        for _ in 0..self.timer.times_finished() {
+            if let Some(finished) = self.next_frame() {
+                return Some(finished);
+            }
+        }
+        None
+    }
+
+    fn next_frame(&mut self) -> Option<String> {
+        let last = self.clip.last.saturating_sub(self.clip.first);
+        match self.clip.playback {
+            Playback::Loop => {
+                self.frame = if self.frame >= last {
+                    0
+                } else {
+                    self.frame + 1
+                };
+            }
+            Playback::Once if self.frame >= last => {
+                let finished = self.name.clone();
+                match self.clip.next.clone() {
+                    Some(next) => self.restart(&next),
+                    None => self.finished = true,
+                }
+                return Some(finished);
+            }
+            Playback::Once => self.frame += 1,
+            Playback::PingPong if last == 0 => {}
+            Playback::PingPong => {
+                if self.frame == 0 {
+                    self.backwards = false;
+                } else if self.frame >= last {
+                    self.backwards = true;
+                }
+                if self.backwards {
+                    self.frame -= 1;
+                } else {
+                    self.frame += 1;
+                }
+            }
+        }
+        None
+    }
+}
+
+fn animation_system(
+    time: Res<Time>,
+    mut events: EventWriter<AnimationFinished>,
+    mut query: Query<(Entity, &mut Animator, &mut TextureAtlasSprite)>,
+) {
+    for (entity, mut animator, mut sprite) in query.iter_mut() {
+        if let Some(clip) = animator.tick(time.delta()) {
+            events.send(AnimationFinished { entity, clip });
+        }
+        if sprite.index != animator.index() {
+            sprite.index = animator.index();
+        }
+    }
+}
+
+fn animation_despawn_system(
+    mut commands: Commands,
+    mut events: EventReader<AnimationFinished>,
+    query: Query<(), With<DespawnOnFinish>>,
+) {
+    for event in events.iter() {
+        if query.get(event.entity).is_ok() {
+            commands.entity(event.entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animator(clips: &[(&str, Clip)], name: &str) -> Animator {
+        let clips = clips
+            .iter()
+            .map(|(name, clip)| (name.to_string(), clip.clone()))
+            .collect();
+        Animator::new(Arc::new(clips), name)
+    }
+
+    /// Frames shown over `count` ticks of one frame each.
+    fn frames(animator: &mut Animator, count: usize) -> Vec<usize> {
+        (0..count)
+            .map(|_| {
+                animator.tick(Duration::from_secs_f32(0.1));
+                animator.index()
+            })
+            .collect()
+    }
+
+    fn clip(first: usize, last: usize, playback: Playback) -> Clip {
+        Clip {
+            first,
+            last,
+            frame_duration: 0.1,
+            playback,
+            next: None,
+        }
+    }
+
+    #[test]
+    fn plays_each_mode() {
+        let mut looping = animator(&[("a", clip(2, 4, Playback::Loop))], "a");
+        assert_eq!(looping.index(), 2);
+        assert_eq!(frames(&mut looping, 4), [3, 4, 2, 3]);
+
+        let mut ping_pong = animator(&[("a", clip(2, 4, Playback::PingPong))], "a");
+        assert_eq!(frames(&mut ping_pong, 6), [3, 4, 3, 2, 3, 4]);
+
+        let mut once = animator(&[("a", clip(2, 4, Playback::Once))], "a");
+        assert_eq!(frames(&mut once, 4), [3, 4, 4, 4]);
+        assert!(once.finished());
+
+        let mut missing = animator(&[], "a");
+        assert_eq!(frames(&mut missing, 2), [0, 0]);
+    }
+
+    #[test]
+    fn finishes_then_moves_on() {
+        let throw = Clip {
+            next: Some("idle".to_string()),
+            ..clip(0, 1, Playback::Once)
+        };
+        let mut animator = animator(
+            &[("throw", throw), ("idle", clip(5, 6, Playback::Loop))],
+            "idle",
+        );
+
+        animator.play("throw");
+        assert_eq!(animator.tick(Duration::from_secs_f32(0.1)), None);
+        assert_eq!(
+            animator.tick(Duration::from_secs_f32(0.1)),
+            Some("throw".to_string())
+        );
+        assert_eq!((animator.clip(), animator.index()), ("idle", 5));
+        assert!(!animator.finished());
+
+        // Playing the same clip again carries on where it was.
+        animator.tick(Duration::from_secs_f32(0.1));
+        animator.play("idle");
+        assert_eq!(animator.index(), 6);
+        animator.restart("idle");
+        assert_eq!(animator.index(), 5);
+    }
+}
@@ -0,0 +1,87 @@
+use std::{fmt, path::PathBuf};
+
+use monkey_fire::config::CONFIG_EXTENSION;
+
+/// Command line options.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// `--seed <seed>`: seed for the game RNG, random if not given.
+    pub seed: Option<u64>,
+    /// `--record <file>`: save the inputs of each game to a replay file.
+    pub record: Option<PathBuf>,
+    /// `--replay <file>`: play back a replay file instead of reading the keyboard.
+    pub replay: Option<PathBuf>,
+    /// `--config <file>`: game configuration in the assets folder, `game.config.ron` if not given.
+    /// Has to end in `.config.ron`, like that one.
+    pub config: Option<String>,
+    /// `--headless <ticks>`: run that many ticks without a window, then print the score.
+    pub headless: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgsError {
+    /// The option given last has no value after it.
+    MissingValue(String),
+    /// The option's value doesn't make sense for it.
+    Invalid {
+        arg: String,
+        value: String,
+    },
+    Unknown(String),
+    /// A `--config` file the asset server wouldn't reload.
+    ConfigName(String),
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgsError::MissingValue(arg) => write!(f, "{} needs a value", arg),
+            ArgsError::Invalid { arg, value } => write!(f, "Invalid {} value: {}", arg, value),
+            ArgsError::Unknown(arg) => write!(f, "Unknown argument: {}", arg),
+            ArgsError::ConfigName(path) => write!(
+                f,
+                "Config files have to end in .{} to be picked up when they change: {}",
+                CONFIG_EXTENSION, path
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArgsError {}
+
+impl Args {
+    pub fn parse() -> Result<Self, ArgsError> {
+        let mut result = Args::default();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| ArgsError::MissingValue(arg.clone()));
+            let number = |value: Result<String, ArgsError>| {
+                let value = value?;
+                value.parse().map_err(|_| ArgsError::Invalid {
+                    arg: arg.clone(),
+                    value,
+                })
+            };
+
+            match arg.as_str() {
+                "--seed" => result.seed = Some(number(value)?),
+                "--headless" => result.headless = Some(number(value)?),
+                "--config" => {
+                    let path = value?;
+                    if !path.ends_with(&format!(".{}", CONFIG_EXTENSION)) {
+                        return Err(ArgsError::ConfigName(path));
+                    }
+                    result.config = Some(path);
+                }
+                "--record" => result.record = Some(value?.into()),
+                "--replay" => result.replay = Some(value?.into()),
+                _ => return Err(ArgsError::Unknown(arg)),
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,182 @@
+//! Packs a folder of animation frames into a sprite sheet and a manifest of its clips.
+//!
+//! ```text
+//! pack_sprites <frames dir> <sheet name> [--2x] [--columns <n>] [--out <dir>]
+//! ```
+//!
+//! Frames are PNG files named after their clip, numbered in the order they play, like
+//! `monkey_run_1.png`. With `--2x` the `@2x` variant of every frame is packed instead. Writes
+//! `<sheet name>.png` and `<sheet name>.manifest.ron` to the assets folder unless told otherwise,
+//! ready to copy into `sprites.manifest.ron`, or `<sheet name>@2x.png` and
+//! `<sheet name>@2x.manifest.ron` with `--2x` so both scales can sit side by side.
+
+use std::{collections::BTreeMap, fmt, fs, path::PathBuf, process};
+
+use monkey_fire::{asset_file, sprites::SpriteManifest};
+
+use crate::pack::PackError;
+
+mod pack;
+
+const USAGE: &str =
+    "Usage: pack_sprites <frames dir> <sheet name> [--2x] [--columns <n>] [--out <dir>]";
+
+/// Command line options.
+#[derive(Debug, Default)]
+struct Args {
+    frames: PathBuf,
+    name: String,
+    /// `--2x`: pack the `@2x` frames.
+    high_dpi: bool,
+    /// `--columns <n>`: frames per row, about square if not given.
+    columns: Option<usize>,
+    /// `--out <dir>`: where to write the sheet, the assets folder if not given.
+    out: Option<PathBuf>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ArgsError {
+    /// The option given last has no value after it.
+    MissingValue(String),
+    /// The option's value doesn't make sense for it.
+    Invalid {
+        arg: String,
+        value: String,
+    },
+    Unknown(String),
+    /// Not exactly a frames dir and a sheet name.
+    Positional,
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgsError::MissingValue(arg) => write!(f, "{} needs a value", arg),
+            ArgsError::Invalid { arg, value } => write!(f, "Invalid {} value: {}", arg, value),
+            ArgsError::Unknown(arg) => write!(f, "Unknown argument: {}", arg),
+            ArgsError::Positional => write!(f, "Expected a frames dir and a sheet name"),
+        }
+    }
+}
+
+impl Args {
+    fn parse() -> Result<Self, ArgsError> {
+        let mut result = Args::default();
+        let mut positional = Vec::new();
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| ArgsError::MissingValue(arg.clone()))
+            };
+
+            match arg.as_str() {
+                "--2x" => result.high_dpi = true,
+                "--columns" => {
+                    let value = value()?;
+                    let columns = value.parse().ok().filter(|columns| *columns > 0);
+                    result.columns = Some(columns.ok_or(ArgsError::Invalid { arg, value })?);
+                }
+                "--out" => result.out = Some(value()?.into()),
+                _ if arg.starts_with("--") => return Err(ArgsError::Unknown(arg)),
+                _ => positional.push(arg),
+            }
+        }
+
+        let [frames, name] =
+            <[String; 2]>::try_from(positional).map_err(|_| ArgsError::Positional)?;
+        result.frames = frames.into();
+        result.name = name;
+        Ok(result)
+    }
+}
+
+fn main() {
+    let args = Args::parse().unwrap_or_else(|err| {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(1);
+    });
+    if let Err(err) = run(&args) {
+        eprintln!("Failed to pack {}: {}", args.frames.display(), err);
+        process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> Result<(), PackError> {
+    let atlas = pack::pack_dir(&args.frames, args.high_dpi, args.columns)?;
+
+    let out = args.out.clone().unwrap_or_else(|| asset_file(""));
+    fs::create_dir_all(&out)?;
+
+    let stem = if args.high_dpi {
+        format!("{}@2x", args.name)
+    } else {
+        args.name.clone()
+    };
+    let image_name = format!("{}.png", stem);
+    let image_path = out.join(&image_name);
+    atlas.image.save(&image_path)?;
+
+    // The manifest wants the image path from the assets folder.
+    let assets = fs::canonicalize(asset_file("")).unwrap_or_default();
+    let asset_path = fs::canonicalize(&image_path)?
+        .strip_prefix(&assets)
+        .map_or(image_name.clone(), |path| {
+            path.to_string_lossy().into_owned()
+        });
+
+    let manifest = SpriteManifest {
+        sheets: BTreeMap::from([(args.name.clone(), atlas.sheet(&asset_path))]),
+    };
+    let ron = ron::ser::to_string_pretty(&manifest, ron::ser::PrettyConfig::default())?;
+    let manifest_path = out.join(format!("{}.manifest.ron", stem));
+    fs::write(&manifest_path, ron + "\n")?;
+
+    println!(
+        "Packed {} clips into {} ({}x{} tiles of {}x{})",
+        atlas.clips.len(),
+        image_path.display(),
+        atlas.columns,
+        atlas.rows,
+        atlas.tile_size.0,
+        atlas.tile_size.1
+    );
+    println!("Wrote {}", manifest_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_both_scales_side_by_side() {
+        let out = std::env::temp_dir().join(format!("pack_sprites_{}", process::id()));
+        let pack = |high_dpi| {
+            run(&Args {
+                frames: "assets/Penguin-images-2/Animations".into(),
+                name: "penguin".into(),
+                high_dpi,
+                out: Some(out.clone()),
+                ..Default::default()
+            })
+            .unwrap();
+        };
+        pack(false);
+        pack(true);
+
+        let sheet = |manifest| {
+            let contents = fs::read_to_string(out.join(manifest)).unwrap();
+            let mut manifest: SpriteManifest = ron::from_str(&contents).unwrap();
+            manifest.sheets.remove("penguin").unwrap()
+        };
+        let normal = sheet("penguin.manifest.ron");
+        let doubled = sheet("penguin@2x.manifest.ron");
+        fs::remove_dir_all(&out).unwrap();
+
+        assert_eq!(normal.path, "penguin.png");
+        assert_eq!(doubled.path, "penguin@2x.png");
+        assert_eq!(doubled.tile_size.0, normal.tile_size.0 * 2.0);
+    }
+}
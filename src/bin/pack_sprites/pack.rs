@@ -0,0 +1,326 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::{imageops, RgbaImage};
+
+use monkey_fire::{
+    error::AssetError,
+    sprites::{Clip, SheetManifest},
+};
+
+const HIGH_DPI_SUFFIX: &str = "@2x";
+
+/// Individual animation frame images, found by [`find_frames`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub path: PathBuf,
+    pub clip: String,
+    /// Position in the clip, from the number at the end of the file name.
+    pub index: u32,
+}
+
+#[derive(Debug)]
+pub enum PackError {
+    Asset(AssetError),
+    /// A frame that isn't a PNG image, or a sheet that couldn't be written as one.
+    Image(image::ImageError),
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackError::Asset(err) => write!(f, "{}", err),
+            PackError::Image(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<AssetError> for PackError {
+    fn from(err: AssetError) -> Self {
+        PackError::Asset(err)
+    }
+}
+
+impl From<io::Error> for PackError {
+    fn from(err: io::Error) -> Self {
+        PackError::Asset(err.into())
+    }
+}
+
+impl From<ron::Error> for PackError {
+    fn from(err: ron::Error) -> Self {
+        PackError::Asset(err.into())
+    }
+}
+
+impl From<image::ImageError> for PackError {
+    fn from(err: image::ImageError) -> Self {
+        PackError::Image(err)
+    }
+}
+
+/// Split a frame's file name into its clip name and frame number, so `monkey_run_3@2x`
+/// is frame 3 of `monkey_run`. Files without a number are frame 0.
+pub fn parse_frame_name(stem: &str) -> (String, u32) {
+    let stem = stem.strip_suffix(HIGH_DPI_SUFFIX).unwrap_or(stem);
+    let name = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let index = stem[name.len()..].parse().unwrap_or(0);
+    let name = name.trim_end_matches(['_', '-', ' ']);
+    (name.to_lowercase(), index)
+}
+
+/// Every PNG frame under `dir`, either the `@2x` variants or the normal ones, sorted by clip
+/// then frame number. Clip names lose the prefix they all share, like `monkey_`.
+pub fn find_frames(dir: &Path, high_dpi: bool) -> Result<Vec<Frame>, PackError> {
+    let mut normal = Vec::new();
+    let mut doubled = Vec::new();
+    for path in png_files(dir)? {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        if stem.ends_with(HIGH_DPI_SUFFIX) {
+            doubled.push(path);
+        } else {
+            normal.push(path);
+        }
+    }
+
+    if high_dpi {
+        let missing: Vec<_> = normal
+            .iter()
+            .filter(|path| !doubled.contains(&high_dpi_path(path)))
+            .map(|path| path.display().to_string())
+            .collect();
+        if !missing.is_empty() {
+            return Err(AssetError::Invalid(vec![format!(
+                "No {} variant of {}",
+                HIGH_DPI_SUFFIX,
+                missing.join(", ")
+            )])
+            .into());
+        }
+    }
+
+    let paths = if high_dpi { doubled } else { normal };
+    if paths.is_empty() {
+        return Err(AssetError::Invalid(vec![format!("No frames in {}", dir.display())]).into());
+    }
+
+    let mut frames: Vec<Frame> = paths
+        .into_iter()
+        .map(|path| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let (clip, index) = parse_frame_name(&stem);
+            Frame { path, clip, index }
+        })
+        .collect();
+
+    let prefix = common_prefix(frames.iter().map(|frame| frame.clip.as_str()));
+    for frame in &mut frames {
+        frame.clip = frame.clip[prefix..].to_string();
+    }
+
+    frames.sort_by(|a, b| (&a.clip, a.index, &a.path).cmp(&(&b.clip, b.index, &b.path)));
+    Ok(frames)
+}
+
+fn png_files(dir: &Path) -> Result<Vec<PathBuf>, PackError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(png_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "png") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+fn high_dpi_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}{}.png", stem, HIGH_DPI_SUFFIX))
+}
+
+/// Length of the prefix up to and including the last `_` shared by every name, as long as
+/// the names aren't all the same.
+fn common_prefix<'a>(mut names: impl Iterator<Item = &'a str>) -> usize {
+    let first = match names.next() {
+        Some(first) => first,
+        None => return 0,
+    };
+
+    let mut shared = first.len();
+    let mut distinct = false;
+    for name in names {
+        if name != first {
+            distinct = true;
+        }
+        shared = first
+            .bytes()
+            .zip(name.bytes())
+            .take(shared)
+            .take_while(|(a, b)| a == b)
+            .count();
+    }
+    if !distinct {
+        return 0;
+    }
+
+    first[..shared].rfind('_').map_or(0, |index| index + 1)
+}
+
+/// Load every frame under `dir` and pack them, keeping each clip's frames together.
+pub fn pack_dir(dir: &Path, high_dpi: bool, columns: Option<usize>) -> Result<Atlas, PackError> {
+    let frames = find_frames(dir, high_dpi)?
+        .into_iter()
+        .map(|frame| Ok((frame.clip, image::open(&frame.path)?.to_rgba8())))
+        .collect::<Result<Vec<_>, PackError>>()?;
+    pack(&frames, columns)
+}
+
+/// A packed atlas, ready to be saved.
+pub struct Atlas {
+    pub image: RgbaImage,
+    pub tile_size: (u32, u32),
+    pub columns: usize,
+    pub rows: usize,
+    pub clips: BTreeMap<String, Clip>,
+}
+
+impl Atlas {
+    /// The manifest entry for this atlas, saved at `path` in the assets folder.
+    pub fn sheet(&self, path: &str) -> SheetManifest {
+        SheetManifest {
+            path: path.to_string(),
+            tile_size: (self.tile_size.0 as f32, self.tile_size.1 as f32),
+            columns: self.columns,
+            rows: self.rows,
+            clips: self.clips.clone(),
+        }
+    }
+}
+
+/// Lay the frames out in a grid, in order, each centred in a tile as big as the largest
+/// frame. Makes a roughly square atlas unless told how many columns to use.
+pub fn pack(frames: &[(String, RgbaImage)], columns: Option<usize>) -> Result<Atlas, PackError> {
+    if frames.is_empty() {
+        return Err(AssetError::Invalid(vec!["Nothing to pack".to_string()]).into());
+    }
+
+    let tile_size = frames.iter().fold((0, 0), |(w, h), (_, image)| {
+        (w.max(image.width()), h.max(image.height()))
+    });
+    let columns = columns
+        .unwrap_or_else(|| (frames.len() as f64).sqrt().ceil() as usize)
+        .clamp(1, frames.len());
+    let rows = frames.len().div_ceil(columns);
+
+    let mut image = RgbaImage::new(tile_size.0 * columns as u32, tile_size.1 * rows as u32);
+    let mut clips: BTreeMap<String, Clip> = BTreeMap::new();
+    for (index, (clip, frame)) in frames.iter().enumerate() {
+        let x = (index % columns) as u32 * tile_size.0 + (tile_size.0 - frame.width()) / 2;
+        let y = (index / columns) as u32 * tile_size.1 + (tile_size.1 - frame.height()) / 2;
+        imageops::overlay(&mut image, frame, x, y);
+
+        let entry = clips
+            .entry(clip.clone())
+            .or_insert_with(|| Clip::new(index, index));
+        if entry.last + 1 != index && entry.first != index {
+            return Err(AssetError::Invalid(vec![format!(
+                "The frames of {} aren't next to each other",
+                clip
+            )])
+            .into());
+        }
+        entry.last = index;
+    }
+
+    Ok(Atlas {
+        image,
+        tile_size,
+        columns,
+        rows,
+        clips,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn clip_names() {
+        assert_eq!(parse_frame_name("monkey_run_3"), ("monkey_run".into(), 3));
+        assert_eq!(
+            parse_frame_name("monkey_run_3@2x"),
+            ("monkey_run".into(), 3)
+        );
+        assert_eq!(parse_frame_name("penguin_die04"), ("penguin_die".into(), 4));
+        assert_eq!(
+            parse_frame_name("Nuclear_explosion10"),
+            ("nuclear_explosion".into(), 10)
+        );
+        assert_eq!(
+            parse_frame_name("monkey_jump_swing_"),
+            ("monkey_jump_swing".into(), 0)
+        );
+        assert_eq!(parse_frame_name("monkey_idle"), ("monkey_idle".into(), 0));
+
+        assert_eq!(common_prefix(["monkey_run", "monkey_idle"].into_iter()), 7);
+        assert_eq!(
+            common_prefix(["ninjacat_walk", "ninjacat_attack_kick"].into_iter()),
+            9
+        );
+        assert_eq!(
+            common_prefix(["nuclear_explosion", "nuclear_explosion"].into_iter()),
+            0
+        );
+        assert_eq!(common_prefix(["run", "idle"].into_iter()), 0);
+    }
+
+    #[test]
+    fn finds_frames_in_order() {
+        let frames = find_frames(Path::new("assets/Penguin-images-2/Animations"), false).unwrap();
+        let names: Vec<_> = frames
+            .iter()
+            .map(|frame| format!("{}{}", frame.clip, frame.index))
+            .collect();
+        assert_eq!(&names[..5], ["die1", "die2", "die3", "die4", "hurt0"]);
+        assert_eq!(frames.len(), 14);
+
+        let doubled = find_frames(Path::new("assets/Penguin-images-2/Animations"), true).unwrap();
+        assert_eq!(doubled.len(), 14);
+        assert!(doubled[0].path.ends_with("penguin_die01@2x.png"));
+    }
+
+    #[test]
+    fn packs_clips_into_a_grid() {
+        let frame = |w, h, value| RgbaImage::from_pixel(w, h, Rgba([value, 0, 0, 255]));
+        let frames = vec![
+            ("idle".to_string(), frame(10, 20, 1)),
+            ("run".to_string(), frame(10, 20, 2)),
+            ("run".to_string(), frame(10, 20, 3)),
+            ("run".to_string(), frame(6, 10, 4)),
+            ("run".to_string(), frame(10, 20, 5)),
+        ];
+
+        let atlas = pack(&frames, None).unwrap();
+        assert_eq!((atlas.columns, atlas.rows), (3, 2));
+        assert_eq!(atlas.tile_size, (10, 20));
+        assert_eq!(atlas.image.dimensions(), (30, 40));
+        assert_eq!(atlas.clips["idle"], Clip::new(0, 0));
+        assert_eq!(atlas.clips["run"], Clip::new(1, 4));
+
+        // The small frame is centred in the first tile of the second row.
+        assert_eq!(atlas.image.get_pixel(0, 20)[3], 0);
+        assert_eq!(atlas.image.get_pixel(2, 25)[0], 4);
+        assert_eq!(atlas.image.get_pixel(10, 20)[0], 5);
+
+        let sheet = atlas.sheet("penguins.png");
+        assert_eq!(sheet.tile_size, (10.0, 20.0));
+    }
+}
@@ -0,0 +1,336 @@
+use bevy::{
+    math::{Vec2, Vec3Swizzles},
+    prelude::{Component, Transform},
+};
+
+/// Collision shape in unscaled sprite pixels, relative to the sprite centre.
+///
+/// Entities without a hitbox collide using their whole `SpriteSize`.
+#[derive(Component, Clone, Debug)]
+pub enum Hitbox {
+    Circle {
+        radius: f32,
+    },
+    Rect {
+        center: Vec2,
+        size: Vec2,
+    },
+    /// Vertices of a convex polygon, in either winding order.
+    Polygon(Vec<Vec2>),
+}
+
+impl Hitbox {
+    pub fn rect(center: (f32, f32), size: (f32, f32)) -> Self {
+        Self::Rect {
+            center: Vec2::new(center.0, center.1),
+            size: Vec2::new(size.0, size.1),
+        }
+    }
+
+    /// Sprite rectangle shrunk by `inset` on every side.
+    pub fn inset(sprite_size: (f32, f32), inset: (f32, f32)) -> Self {
+        Self::Rect {
+            center: Vec2::ZERO,
+            size: Vec2::new(sprite_size.0 - inset.0 * 2.0, sprite_size.1 - inset.1 * 2.0),
+        }
+    }
+
+    pub fn polygon(points: &[(f32, f32)]) -> Self {
+        Self::Polygon(points.iter().map(|&(x, y)| Vec2::new(x, y)).collect())
+    }
+
+    /// Place the hitbox in the world. Polygons mirror with a flipped sprite.
+    pub fn shape(&self, tf: &Transform) -> Shape {
+        let position = tf.translation.xy();
+        let scale = tf.scale.xy();
+
+        match self {
+            Hitbox::Circle { radius } => Shape::Circle {
+                center: position,
+                radius: radius * scale.x.abs().max(scale.y.abs()),
+            },
+            Hitbox::Rect { center, size } => Shape::Rect {
+                center: position + *center * scale,
+                half_size: *size * scale.abs() / 2.0,
+            },
+            Hitbox::Polygon(points) => {
+                Shape::Polygon(points.iter().map(|&p| position + p * scale).collect())
+            }
+        }
+    }
+}
+
+/// A hitbox placed in world coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Circle { center: Vec2, radius: f32 },
+    Rect { center: Vec2, half_size: Vec2 },
+    Polygon(Vec<Vec2>),
+}
+
+impl Shape {
+    /// The default shape, covering the whole scaled sprite.
+    pub fn from_sprite(tf: &Transform, sprite_size: Vec2) -> Self {
+        Shape::Rect {
+            center: tf.translation.xy(),
+            half_size: sprite_size * tf.scale.xy().abs() / 2.0,
+        }
+    }
+
+    /// Centre and size of the axis aligned box around the shape.
+    pub fn bounding_box(&self) -> (Vec2, Vec2) {
+        match self {
+            Shape::Circle { center, radius } => (*center, Vec2::splat(radius * 2.0)),
+            Shape::Rect { center, half_size } => (*center, *half_size * 2.0),
+            Shape::Polygon(points) => {
+                let min = points.iter().fold(Vec2::splat(f32::MAX), |m, &p| m.min(p));
+                let max = points.iter().fold(Vec2::splat(f32::MIN), |m, &p| m.max(p));
+                ((min + max) / 2.0, max - min)
+            }
+        }
+    }
+
+    pub fn intersects(&self, other: &Shape) -> bool {
+        match (self, other) {
+            (
+                Shape::Circle {
+                    center: a,
+                    radius: ra,
+                },
+                Shape::Circle {
+                    center: b,
+                    radius: rb,
+                },
+            ) => a.distance_squared(*b) < (ra + rb) * (ra + rb),
+            (
+                Shape::Circle { center, radius },
+                Shape::Rect {
+                    center: rect_center,
+                    half_size,
+                },
+            )
+            | (
+                Shape::Rect {
+                    center: rect_center,
+                    half_size,
+                },
+                Shape::Circle { center, radius },
+            ) => {
+                let closest = center.clamp(*rect_center - *half_size, *rect_center + *half_size);
+                closest.distance_squared(*center) < radius * radius
+            }
+            (Shape::Circle { center, radius }, Shape::Polygon(points))
+            | (Shape::Polygon(points), Shape::Circle { center, radius }) => {
+                circle_polygon(*center, *radius, points)
+            }
+            (
+                Shape::Rect {
+                    center: a,
+                    half_size: ha,
+                },
+                Shape::Rect {
+                    center: b,
+                    half_size: hb,
+                },
+            ) => {
+                let distance = (*a - *b).abs();
+                distance.x < ha.x + hb.x && distance.y < ha.y + hb.y
+            }
+            (Shape::Rect { center, half_size }, Shape::Polygon(points))
+            | (Shape::Polygon(points), Shape::Rect { center, half_size }) => {
+                polygon_polygon(&rect_points(*center, *half_size), points)
+            }
+            (Shape::Polygon(a), Shape::Polygon(b)) => polygon_polygon(a, b),
+        }
+    }
+}
+
+fn rect_points(center: Vec2, half_size: Vec2) -> [Vec2; 4] {
+    [
+        center + Vec2::new(-half_size.x, -half_size.y),
+        center + Vec2::new(half_size.x, -half_size.y),
+        center + Vec2::new(half_size.x, half_size.y),
+        center + Vec2::new(-half_size.x, half_size.y),
+    ]
+}
+
+fn edge_normals(points: &[Vec2]) -> impl Iterator<Item = Vec2> + '_ {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(&a, &b)| (b - a).perp())
+}
+
+fn project(points: &[Vec2], axis: Vec2) -> (f32, f32) {
+    points.iter().fold((f32::MAX, f32::MIN), |(min, max), p| {
+        let d = p.dot(axis);
+        (min.min(d), max.max(d))
+    })
+}
+
+/// Separating axis test between two convex polygons.
+fn polygon_polygon(a: &[Vec2], b: &[Vec2]) -> bool {
+    edge_normals(a).chain(edge_normals(b)).all(|axis| {
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        a_min < b_max && b_min < a_max
+    })
+}
+
+/// Separating axis test between a circle and a convex polygon.
+fn circle_polygon(center: Vec2, radius: f32, points: &[Vec2]) -> bool {
+    let closest = points
+        .iter()
+        .copied()
+        .min_by(|a, b| {
+            a.distance_squared(center)
+                .total_cmp(&b.distance_squared(center))
+        })
+        .unwrap_or(center);
+
+    edge_normals(points)
+        .chain(std::iter::once(closest - center))
+        .filter(|axis| *axis != Vec2::ZERO)
+        .all(|axis| {
+            let axis = axis.normalize();
+            let (min, max) = project(points, axis);
+            let c = center.dot(axis);
+            c - radius < max && min < c + radius
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::Vec3;
+
+    use super::*;
+
+    fn circle(x: f32, y: f32, radius: f32) -> Shape {
+        Shape::Circle {
+            center: Vec2::new(x, y),
+            radius,
+        }
+    }
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Shape {
+        Shape::Rect {
+            center: Vec2::new(x, y),
+            half_size: Vec2::new(w / 2.0, h / 2.0),
+        }
+    }
+
+    /// A diamond with its corners `r` away from the centre.
+    fn diamond(x: f32, y: f32, r: f32) -> Shape {
+        Shape::Polygon(vec![
+            Vec2::new(x + r, y),
+            Vec2::new(x, y + r),
+            Vec2::new(x - r, y),
+            Vec2::new(x, y - r),
+        ])
+    }
+
+    fn assert_symmetric(a: &Shape, b: &Shape, expected: bool) {
+        assert_eq!(a.intersects(b), expected, "{:?} vs {:?}", a, b);
+        assert_eq!(b.intersects(a), expected, "{:?} vs {:?}", b, a);
+    }
+
+    #[test]
+    fn circle_circle() {
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &circle(15.0, 0.0, 10.0), true);
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &circle(20.0, 0.0, 10.0), false);
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &circle(15.0, 15.0, 10.0), false);
+    }
+
+    #[test]
+    fn circle_rect() {
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &rect(15.0, 0.0, 20.0, 20.0), true);
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &rect(25.0, 0.0, 20.0, 20.0), false);
+        // Near the corner the rounded circle misses where its bounding box would hit.
+        assert_symmetric(
+            &circle(0.0, 0.0, 10.0),
+            &rect(18.0, 18.0, 20.0, 20.0),
+            false,
+        );
+    }
+
+    #[test]
+    fn circle_polygon() {
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &diamond(15.0, 0.0, 10.0), true);
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &diamond(21.0, 0.0, 10.0), false);
+        // The diagonal edge of the diamond passes outside the circle.
+        assert_symmetric(&circle(0.0, 0.0, 10.0), &diamond(14.0, 14.0, 10.0), false);
+    }
+
+    #[test]
+    fn rect_rect() {
+        assert_symmetric(
+            &rect(0.0, 0.0, 20.0, 20.0),
+            &rect(15.0, 15.0, 20.0, 20.0),
+            true,
+        );
+        assert_symmetric(
+            &rect(0.0, 0.0, 20.0, 20.0),
+            &rect(20.0, 0.0, 20.0, 20.0),
+            false,
+        );
+        assert_symmetric(
+            &rect(0.0, 0.0, 20.0, 20.0),
+            &rect(0.0, 25.0, 20.0, 20.0),
+            false,
+        );
+    }
+
+    #[test]
+    fn rect_polygon() {
+        assert_symmetric(&rect(0.0, 0.0, 20.0, 20.0), &diamond(15.0, 0.0, 10.0), true);
+        assert_symmetric(
+            &rect(0.0, 0.0, 20.0, 20.0),
+            &diamond(21.0, 0.0, 10.0),
+            false,
+        );
+        // Bounding boxes overlap but the diamond's edge clears the corner.
+        assert_symmetric(
+            &rect(0.0, 0.0, 20.0, 20.0),
+            &diamond(18.0, 18.0, 10.0),
+            false,
+        );
+    }
+
+    #[test]
+    fn polygon_polygon() {
+        assert_symmetric(&diamond(0.0, 0.0, 10.0), &diamond(15.0, 0.0, 10.0), true);
+        assert_symmetric(&diamond(0.0, 0.0, 10.0), &diamond(20.0, 0.0, 10.0), false);
+        assert_symmetric(&diamond(0.0, 0.0, 10.0), &diamond(9.0, 9.0, 10.0), true);
+        assert_symmetric(&diamond(0.0, 0.0, 10.0), &diamond(11.0, 11.0, 10.0), false);
+    }
+
+    #[test]
+    fn hitbox_follows_transform() {
+        let tf = Transform {
+            translation: Vec3::new(100.0, 50.0, 0.0),
+            scale: Vec3::new(-0.5, 0.5, 1.0),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            Hitbox::Circle { radius: 20.0 }.shape(&tf),
+            circle(100.0, 50.0, 10.0)
+        );
+        assert_eq!(
+            Hitbox::inset((100.0, 80.0), (10.0, 20.0)).shape(&tf),
+            rect(100.0, 50.0, 40.0, 20.0)
+        );
+        assert_eq!(
+            Hitbox::polygon(&[(10.0, 0.0), (0.0, 10.0), (0.0, -10.0)]).shape(&tf),
+            Shape::Polygon(vec![
+                Vec2::new(95.0, 50.0),
+                Vec2::new(100.0, 55.0),
+                Vec2::new(100.0, 45.0)
+            ])
+        );
+        assert_eq!(
+            Shape::from_sprite(&tf, Vec2::new(100.0, 80.0)),
+            rect(100.0, 50.0, 50.0, 40.0)
+        );
+    }
+}
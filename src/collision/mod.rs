@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    components::{Invulnerable, SpriteSize},
+    simulation::SimulationStage,
+};
+
+pub use self::{
+    hitbox::{Hitbox, Shape},
+    spatial_hash::SpatialHash,
+};
+
+mod hitbox;
+mod spatial_hash;
+
+/// Should be larger than most colliders, so each one only lands in a few cells.
+const SPATIAL_HASH_CELL_SIZE: f32 = 128.0;
+
+pub struct CollisionPlugin;
+
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CollisionEvent>()
+            .insert_resource(SpatialHash::new(SPATIAL_HASH_CELL_SIZE))
+            .add_system_set_to_stage(
+                SimulationStage,
+                SystemSet::new()
+                    .with_system(spatial_hash_system)
+                    .with_system(
+                        collision_detection_system
+                            .label(CollisionLabel)
+                            .after(spatial_hash_system),
+                    ),
+            );
+    }
+}
+
+/// Label for the collision detection system, consumers of [`CollisionEvent`] run after it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct CollisionLabel;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CollisionLayer {
+    Player,
+    PlayerFire,
+    Enemy,
+    EnemyFire,
+}
+
+impl CollisionLayer {
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Which layer an entity lives on, and which layers it can hit.
+#[derive(Component, Copy, Clone, Debug)]
+pub struct CollisionLayers {
+    pub layer: CollisionLayer,
+    mask: u32,
+}
+
+impl CollisionLayers {
+    pub fn new(layer: CollisionLayer, hits: &[CollisionLayer]) -> Self {
+        let mask = hits.iter().fold(0, |mask, layer| mask | layer.bit());
+        Self { layer, mask }
+    }
+
+    pub fn hits(&self, other: &CollisionLayers) -> bool {
+        self.mask & other.layer.bit() != 0
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Collided {
+    pub entity: Entity,
+    pub layer: CollisionLayer,
+    pub translation: Vec3,
+}
+
+/// Sent when `source` hits `target`, according to the mask of `source`.
+///
+/// An entity takes part in at most one collision per frame.
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionEvent {
+    pub source: Collided,
+    pub target: Collided,
+}
+
+impl CollisionEvent {
+    pub fn layers(&self) -> (CollisionLayer, CollisionLayer) {
+        (self.source.layer, self.target.layer)
+    }
+}
+
+type ColliderQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Transform,
+        &'static SpriteSize,
+        Option<&'static Hitbox>,
+        &'static CollisionLayers,
+    ),
+    Without<Invulnerable>,
+>;
+
+pub fn collider_shape(tf: &Transform, size: &SpriteSize, hitbox: Option<&Hitbox>) -> Shape {
+    match hitbox {
+        Some(hitbox) => hitbox.shape(tf),
+        None => Shape::from_sprite(tf, size.0),
+    }
+}
+
+fn spatial_hash_system(mut spatial_hash: ResMut<SpatialHash>, query: ColliderQuery) {
+    spatial_hash.clear();
+
+    for (entity, tf, size, hitbox, _) in query.iter() {
+        let (center, size) = collider_shape(tf, size, hitbox).bounding_box();
+        spatial_hash.insert(entity, center, size);
+    }
+}
+
+fn collision_detection_system(
+    mut events: EventWriter<CollisionEvent>,
+    spatial_hash: Res<SpatialHash>,
+    query: ColliderQuery,
+) {
+    let mut collided_entities: HashSet<Entity> = HashSet::new();
+    let mut candidates: Vec<Entity> = Vec::new();
+
+    for (a_entity, a_tf, a_size, a_hitbox, a_layers) in query.iter() {
+        if collided_entities.contains(&a_entity) {
+            continue;
+        }
+
+        let a_shape = collider_shape(a_tf, a_size, a_hitbox);
+        let (center, size) = a_shape.bounding_box();
+        spatial_hash.query(center, size, &mut candidates);
+
+        for &b_entity in candidates.iter() {
+            if a_entity == b_entity || collided_entities.contains(&b_entity) {
+                continue;
+            }
+
+            let (_, b_tf, b_size, b_hitbox, b_layers) = match query.get(b_entity) {
+                Ok(collider) => collider,
+                Err(_) => continue,
+            };
+
+            if !a_layers.hits(b_layers) {
+                continue;
+            }
+
+            if a_shape.intersects(&collider_shape(b_tf, b_size, b_hitbox)) {
+                collided_entities.insert(a_entity);
+                collided_entities.insert(b_entity);
+
+                events.send(CollisionEvent {
+                    source: Collided {
+                        entity: a_entity,
+                        layer: a_layers.layer,
+                        translation: a_tf.translation,
+                    },
+                    target: Collided {
+                        entity: b_entity,
+                        layer: b_layers.layer,
+                        translation: b_tf.translation,
+                    },
+                });
+
+                break;
+            }
+        }
+    }
+}
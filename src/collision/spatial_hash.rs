@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use bevy::{ecs::entity::Entity, math::Vec2};
+
+/// Uniform grid that buckets entities by the cells their bounding box overlaps.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Empty every cell, keeping the allocations for the next rebuild.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    pub fn insert(&mut self, entity: Entity, center: Vec2, size: Vec2) {
+        let (min, max) = self.cell_range(center, size);
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                self.cells.entry((x, y)).or_default().push(entity);
+            }
+        }
+    }
+
+    /// Collect every entity sharing a cell with the given box into `found`, without duplicates.
+    pub fn query(&self, center: Vec2, size: Vec2, found: &mut Vec<Entity>) {
+        found.clear();
+
+        let (min, max) = self.cell_range(center, size);
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    found.extend_from_slice(cell);
+                }
+            }
+        }
+
+        found.sort_unstable();
+        found.dedup();
+    }
+
+    fn cell_range(&self, center: Vec2, size: Vec2) -> ((i32, i32), (i32, i32)) {
+        let min = (center - size / 2.0) / self.cell_size;
+        let max = (center + size / 2.0) / self.cell_size;
+        (
+            (min.x.floor() as i32, min.y.floor() as i32),
+            (max.x.floor() as i32, max.y.floor() as i32),
+        )
+    }
+}
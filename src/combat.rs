@@ -0,0 +1,222 @@
+use bevy::prelude::*;
+
+use crate::{
+    animation::{Animator, DespawnOnFinish},
+    collision::{Collided, CollisionEvent, CollisionLabel, CollisionLayer, CollisionPlugin},
+    components::{Damage, Dying, EnemyKind, Explosion, ExplosionToSpawn, Health, Hurt},
+    enemy::EnemyTypes,
+    simulation::{SimulationClock, SimulationStage},
+    EnemyCount, GameTextures, PlayerState, Scoreboard, HURT_COLOR,
+};
+
+/// What happens when things collide: damage, score and explosions.
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(CollisionPlugin)
+            .add_event::<DestroyedEvent>()
+            .add_system_set_to_stage(
+                SimulationStage,
+                SystemSet::new()
+                    .with_system(
+                        collision_damage_system
+                            .label(DamageLabel)
+                            .after(CollisionLabel),
+                    )
+                    .with_system(destroyed_score_system.after(DamageLabel))
+                    .with_system(hurt_end_system.after(DamageLabel))
+                    .with_system(dying_system)
+                    .with_system(destroyed_explosion_system.after(DamageLabel)),
+            )
+            .add_system(explosion_to_spawn_system)
+            .add_system(hurt_tint_system);
+    }
+}
+
+/// Label for the system dealing out damage, consumers of [`DestroyedEvent`] run after it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct DamageLabel;
+
+/// Sent when a collision leaves `target` destroyed.
+#[derive(Copy, Clone, Debug)]
+pub struct DestroyedEvent {
+    pub target: Collided,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collision_damage_system(
+    mut commands: Commands,
+    mut events: EventReader<CollisionEvent>,
+    mut destroyed: EventWriter<DestroyedEvent>,
+    mut enemy_count: ResMut<EnemyCount>,
+    mut player_state: ResMut<PlayerState>,
+    clock: Res<SimulationClock>,
+    damage_query: Query<&Damage>,
+    mut health_query: Query<&mut Health>,
+) {
+    for event in events.iter() {
+        match event.layers() {
+            (CollisionLayer::PlayerFire, CollisionLayer::Enemy)
+            | (CollisionLayer::EnemyFire, CollisionLayer::Player) => {
+                commands.entity(event.source.entity).despawn();
+            }
+            (CollisionLayer::PlayerFire, CollisionLayer::EnemyFire) => {}
+            _ => continue,
+        }
+
+        // Anything without health goes down in one hit.
+        let target = event.target.entity;
+        if let Ok(mut health) = health_query.get_mut(target) {
+            let damage = damage_query
+                .get(event.source.entity)
+                .map_or(1, |damage| damage.0);
+            health.0 = health.0.saturating_sub(damage);
+            if health.0 > 0 {
+                commands
+                    .entity(target)
+                    .insert(Hurt::starting_at(clock.tick));
+                continue;
+            }
+        }
+
+        commands.entity(target).despawn();
+        match event.target.layer {
+            CollisionLayer::Enemy => {
+                enemy_count.0 -= 1;
+            }
+            CollisionLayer::Player => player_state.shot(clock.seconds()),
+            _ => {}
+        }
+        destroyed.send(DestroyedEvent {
+            target: event.target,
+        });
+    }
+}
+
+fn destroyed_score_system(
+    mut events: EventReader<DestroyedEvent>,
+    mut scoreboard: ResMut<Scoreboard>,
+    enemy_types: Res<EnemyTypes>,
+    query: Query<&EnemyKind>,
+) {
+    for event in events.iter() {
+        match event.target.layer {
+            CollisionLayer::Enemy => {
+                let score = query
+                    .get(event.target.entity)
+                    .ok()
+                    .and_then(|kind| enemy_types.get(kind.0))
+                    .map_or(1, |enemy_type| enemy_type.score);
+                scoreboard.score = scoreboard.score.saturating_add(score);
+            }
+            CollisionLayer::Player => {
+                scoreboard.score = scoreboard.score.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn destroyed_explosion_system(mut commands: Commands, mut events: EventReader<DestroyedEvent>) {
+    for event in events.iter() {
+        // The player explodes once it has finished dying.
+        if event.target.layer == CollisionLayer::Player {
+            continue;
+        }
+
+        commands
+            .spawn()
+            .insert(ExplosionToSpawn(event.target.translation));
+    }
+}
+
+fn explosion_to_spawn_system(
+    mut commands: Commands,
+    game_textures: Res<GameTextures>,
+    query: Query<(Entity, &ExplosionToSpawn)>,
+) {
+    let sheet = &game_textures.explosion;
+
+    for (explosion_spawn_entity, explosion_to_spawn) in query.iter() {
+        let animator = sheet.animator("explode");
+        commands
+            .spawn_bundle(SpriteSheetBundle {
+                texture_atlas: sheet.atlas.clone(),
+                sprite: TextureAtlasSprite {
+                    index: animator.index(),
+                    ..Default::default()
+                },
+                transform: Transform {
+                    translation: explosion_to_spawn.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(Explosion)
+            .insert(animator)
+            .insert(DespawnOnFinish);
+
+        commands.entity(explosion_spawn_entity).despawn();
+    }
+}
+
+/// Blow up bodies once they're done dying.
+fn dying_system(
+    mut commands: Commands,
+    clock: Res<SimulationClock>,
+    query: Query<(Entity, &Transform, &Dying)>,
+) {
+    for (entity, transform, dying) in query.iter() {
+        if clock.tick >= dying.until {
+            commands
+                .spawn()
+                .insert(ExplosionToSpawn(transform.translation));
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Stop being hurt once the time is up.
+fn hurt_end_system(
+    mut commands: Commands,
+    clock: Res<SimulationClock>,
+    query: Query<(Entity, &Hurt)>,
+) {
+    for (entity, hurt) in query.iter() {
+        if clock.tick >= hurt.until {
+            commands.entity(entity).remove::<Hurt>();
+        }
+    }
+}
+
+/// Tint whatever is hurt, and play its hurt clip if it has one when it gets hit. Only looks,
+/// the simulation decides how long it's hurt for.
+#[allow(clippy::type_complexity)]
+fn hurt_tint_system(
+    mut query: Query<
+        (
+            Option<ChangeTrackers<Hurt>>,
+            &mut TextureAtlasSprite,
+            Option<&mut Animator>,
+        ),
+        With<Health>,
+    >,
+) {
+    for (hurt, mut sprite, animator) in query.iter_mut() {
+        let color = if hurt.is_some() {
+            HURT_COLOR
+        } else {
+            Color::WHITE
+        };
+        if sprite.color != color {
+            sprite.color = color;
+        }
+
+        if hurt.is_some_and(|hurt| hurt.is_changed()) {
+            if let Some(mut animator) = animator.filter(|animator| animator.has_clip("hurt")) {
+                animator.restart("hurt");
+            }
+        }
+    }
+}
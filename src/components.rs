@@ -1,11 +1,11 @@
-use std::ops::RangeInclusive;
-
 use bevy::{
     core::Timer,
     math::{Vec2, Vec3},
     prelude::Component,
 };
 
+use crate::{simulation::ticks, GameState, DYING_DURATION, HURT_DURATION};
+
 #[derive(Component)]
 pub struct Velocity {
     pub x: f32,
@@ -37,41 +37,101 @@ pub struct Movable {
 #[derive(Component)]
 pub struct Player;
 
+/// Ignored by collision detection until the timer runs out, blinking while it lasts.
 #[derive(Component)]
-pub struct FromPlayer;
+pub struct Invulnerable {
+    pub timer: Timer,
+    pub blink: Timer,
+}
+
+impl Invulnerable {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(duration, false),
+            blink: Timer::from_seconds(0.1, true),
+        }
+    }
+}
 
 #[derive(Component)]
 pub struct Enemy;
 
-#[derive(Component)]
-pub struct FromEnemy;
+/// Hits left before being destroyed.
+#[derive(Component, Debug)]
+pub struct Health(pub u32);
 
-#[derive(Component)]
-pub struct Explosion;
+/// Health taken from whatever this hits.
+#[derive(Component, Debug)]
+pub struct Damage(pub u32);
 
-#[derive(Component)]
-pub struct ExplosionToSpawn(pub Vec3);
-
-#[derive(Component)]
-pub struct ExplosionTimer(pub Timer);
+/// Just hit without being destroyed, tinted until simulation tick `until`.
+#[derive(Component, Debug)]
+pub struct Hurt {
+    pub until: u64,
+}
 
-impl Default for ExplosionTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(0.1, true))
+impl Hurt {
+    /// Hurt from `tick` for the `HURT_DURATION`.
+    pub fn starting_at(tick: u64) -> Self {
+        Self {
+            until: tick + ticks(HURT_DURATION),
+        }
     }
 }
 
+/// Simulation tick the enemy came out on. No two enemies come out on the same tick, so
+/// visiting them in this order keeps the simulation the same whatever the entity ids are.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SpawnTick(pub u64);
+
+/// Name of the enemy's type in the `EnemyTypes`.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct EnemyKind(pub &'static str);
+
+/// Times per second the enemy fires, instead of the `enemy_fire_rate`.
+#[derive(Component, Debug)]
+pub struct FireRate(pub f64);
+
 #[derive(Component)]
-pub struct Animate {
-    pub range: RangeInclusive<usize>,
-    pub timer: Timer,
+pub struct Explosion;
+
+#[derive(Component)]
+pub struct ExplosionToSpawn(pub Vec3);
+
+/// Plays its death animation, then explodes on simulation tick `until`.
+#[derive(Component, Debug)]
+pub struct Dying {
+    pub until: u64,
 }
 
-impl Default for Animate {
-    fn default() -> Self {
+impl Dying {
+    /// Dying from `tick` for the `DYING_DURATION`.
+    pub fn starting_at(tick: u64) -> Self {
         Self {
-            range: 0..=0,
-            timer: Timer::from_seconds(0.5, true),
+            until: tick + ticks(DYING_DURATION),
         }
     }
 }
+
+#[derive(Component)]
+pub struct ScoreText;
+
+#[derive(Component)]
+pub struct ScreenText(pub GameState);
+
+#[derive(Component)]
+pub struct LivesDisplay;
+
+#[derive(Component)]
+pub struct WaveText;
+
+/// Announces the next wave during the breather before it.
+#[derive(Component)]
+pub struct WaveBanner;
+
+/// Shown while there's a boss, with a `BossHealthFill` as wide as its health.
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossHealthFill;
@@ -0,0 +1,323 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asset_file, error::AssetError, BASE_SPEED, BOSS_EVERY, ENEMY_FIRE_RATE, ENEMY_MAX,
+    FORMATION_MEMBERS_MAX, PLAYER_EXTRA_LIFE_SCORE, PLAYER_INVULNERABLE_DURATION, PLAYER_LIVES,
+    PLAYER_RESPAWN_DELAY, SPRITE_SCALE, TIME_STEP, WAVE_BREATHER, WAVE_ENEMIES, WAVE_ESCALATION,
+    WAVE_GROWTH,
+};
+
+/// Path of the config file in the assets folder.
+pub const CONFIG_ASSET: &str = "game.config.ron";
+
+/// What config file names end in, for the asset server to know them when they change.
+pub const CONFIG_EXTENSION: &str = "config.ron";
+
+/// Picks up changes to the config file while the game runs. Needs the `AssetPlugin`.
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<GameConfig>()
+            .init_asset_loader::<GameConfigLoader>()
+            .init_resource::<ConfigAsset>()
+            .add_startup_system(config_watch_system)
+            .add_system(config_reload_system);
+    }
+}
+
+/// The config file being used, relative to the assets folder.
+pub struct ConfigAsset {
+    pub path: String,
+    handle: Option<Handle<GameConfig>>,
+}
+
+impl ConfigAsset {
+    pub fn new(path: String) -> Self {
+        Self { path, handle: None }
+    }
+
+    pub fn file(&self) -> PathBuf {
+        asset_file(&self.path)
+    }
+}
+
+impl Default for ConfigAsset {
+    fn default() -> Self {
+        Self::new(CONFIG_ASSET.to_string())
+    }
+}
+
+/// Tuning values for the game. Anything left out of the config file keeps its default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "0b8f5a2e-6c1d-4f0a-9a43-7e2d51c8b6f3"]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub sprite_scale: f32,
+    /// Pixels per second moved at a velocity of 1.
+    pub base_speed: f32,
+    pub player_lives: u32,
+    /// Score needed for each extra life.
+    pub player_extra_life_score: u32,
+    /// Seconds before the player comes back after being shot.
+    pub player_respawn_delay: f64,
+    /// Seconds the player can't be hit after spawning.
+    pub player_invulnerable_duration: f32,
+    pub enemy_max: u32,
+    /// Enemies sharing a formation before a new one is made.
+    pub formation_members_max: u32,
+    /// Average number of times per second each enemy fires.
+    pub enemy_fire_rate: f64,
+    /// Enemies in the first wave.
+    pub wave_enemies: u32,
+    /// Extra enemies in each wave after that.
+    pub wave_growth: u32,
+    /// Seconds between waves.
+    pub wave_breather: f64,
+    /// How much faster enemies move and fire each wave, as a fraction of the first wave.
+    pub wave_escalation: f32,
+    /// Every this many waves is a boss wave, unless the wave script has it. 0 for no bosses.
+    pub boss_every: u32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            sprite_scale: SPRITE_SCALE,
+            base_speed: BASE_SPEED,
+            player_lives: PLAYER_LIVES,
+            player_extra_life_score: PLAYER_EXTRA_LIFE_SCORE,
+            player_respawn_delay: PLAYER_RESPAWN_DELAY,
+            player_invulnerable_duration: PLAYER_INVULNERABLE_DURATION,
+            enemy_max: ENEMY_MAX,
+            formation_members_max: FORMATION_MEMBERS_MAX,
+            enemy_fire_rate: ENEMY_FIRE_RATE,
+            wave_enemies: WAVE_ENEMIES,
+            wave_growth: WAVE_GROWTH,
+            wave_breather: WAVE_BREATHER,
+            wave_escalation: WAVE_ESCALATION,
+            boss_every: BOSS_EVERY,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Load and validate the config straight away, or use the defaults if there is no such file.
+    pub fn load(path: &Path) -> Result<Self, AssetError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::from_ron(&contents),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, AssetError> {
+        let config: Self = ron::from_str(contents)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), AssetError> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: &str| {
+            if !ok {
+                problems.push(problem.to_string());
+            }
+        };
+
+        check(self.sprite_scale > 0.0, "sprite_scale must be above 0");
+        check(self.base_speed > 0.0, "base_speed must be above 0");
+        check(self.player_lives > 0, "player_lives must be at least 1");
+        check(
+            self.player_extra_life_score > 0,
+            "player_extra_life_score must be at least 1",
+        );
+        check(
+            self.player_respawn_delay >= 0.0,
+            "player_respawn_delay can't be negative",
+        );
+        check(
+            self.player_invulnerable_duration >= 0.0,
+            "player_invulnerable_duration can't be negative",
+        );
+        check(
+            self.formation_members_max > 0,
+            "formation_members_max must be at least 1",
+        );
+        let max_fire_rate = 1.0 / TIME_STEP as f64;
+        check(
+            (0.0..=max_fire_rate).contains(&self.enemy_fire_rate),
+            &format!("enemy_fire_rate must be between 0 and {}", max_fire_rate),
+        );
+        check(self.wave_enemies > 0, "wave_enemies must be at least 1");
+        check(self.wave_breather >= 0.0, "wave_breather can't be negative");
+        check(
+            self.wave_escalation >= 0.0,
+            "wave_escalation can't be negative",
+        );
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AssetError::Invalid(problems))
+        }
+    }
+
+    /// Chance of an enemy firing on any one tick.
+    pub fn enemy_fire_chance(&self) -> f64 {
+        self.enemy_fire_rate * TIME_STEP as f64
+    }
+
+    /// Describe every value that differs in `other`, as `name: old -> new`.
+    pub fn changes(&self, other: &GameConfig) -> Vec<String> {
+        let old = self.values();
+        let mut new = other.values();
+
+        old.iter()
+            .filter_map(|(name, old_value)| {
+                let new_value = new
+                    .remove(name)
+                    .filter(|new_value| new_value != old_value)?;
+                let name = match name {
+                    ron::Value::String(name) => name.clone(),
+                    name => ron::to_string(name).ok()?,
+                };
+                Some(format!(
+                    "{}: {} -> {}",
+                    name,
+                    ron::to_string(old_value).ok()?,
+                    ron::to_string(&new_value).ok()?
+                ))
+            })
+            .collect()
+    }
+
+    /// Every value, by field name.
+    fn values(&self) -> ron::Map {
+        match ron::to_string(self).map(|ron| ron::from_str(&ron)) {
+            Ok(Ok(ron::Value::Map(values))) => values,
+            _ => ron::Map::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let config = GameConfig::from_ron(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &[CONFIG_EXTENSION]
+    }
+}
+
+fn config_watch_system(mut config_asset: ResMut<ConfigAsset>, asset_server: Res<AssetServer>) {
+    if !config_asset.file().exists() {
+        info!("No {}, using the default config", config_asset.path);
+        return;
+    }
+
+    if let Err(err) = asset_server.watch_for_changes() {
+        warn!("Config changes won't be picked up: {:?}", err);
+    }
+    config_asset.handle = Some(asset_server.load(&config_asset.path));
+}
+
+fn config_reload_system(
+    mut events: EventReader<AssetEvent<GameConfig>>,
+    config_asset: Res<ConfigAsset>,
+    configs: Res<Assets<GameConfig>>,
+    mut config: ResMut<GameConfig>,
+) {
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        if Some(handle) != config_asset.handle.as_ref() {
+            continue;
+        }
+
+        if let Some(new_config) = configs.get(handle) {
+            let changes = config.changes(new_config);
+            if !changes.is_empty() {
+                info!(
+                    "Config {} changed: {}",
+                    config_asset.path,
+                    changes.join(", ")
+                );
+                *config = new_config.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_config_is_the_defaults() {
+        let config = GameConfig::from_ron(include_str!("../assets/game.config.ron")).unwrap();
+        assert_eq!(config, GameConfig::default());
+    }
+
+    #[test]
+    fn missing_values_are_defaults() {
+        let config = GameConfig::from_ron("(enemy_max: 5)").unwrap();
+        assert_eq!(config.enemy_max, 5);
+        assert_eq!(config.base_speed, BASE_SPEED);
+    }
+
+    #[test]
+    fn describes_changes() {
+        let old = GameConfig::default();
+        let new = GameConfig {
+            base_speed: 600.0,
+            enemy_max: 4,
+            ..old.clone()
+        };
+
+        assert!(old.changes(&old).is_empty());
+        assert_eq!(
+            old.changes(&new),
+            vec!["base_speed: 500 -> 600", "enemy_max: 2 -> 4"]
+        );
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let err = GameConfig::from_ron("(base_speed: 0, player_lives: 0)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "base_speed must be above 0; player_lives must be at least 1"
+        );
+
+        assert!(matches!(
+            GameConfig::from_ron("(base_sped: 10)"),
+            Err(AssetError::Ron(_))
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+use std::f32::consts::PI;
+
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    collision::{collider_shape, CollisionLayers, Hitbox, Shape},
+    components::{SpriteSize, Velocity},
+    config::GameConfig,
+    enemy::Formation,
+};
+
+const DEBUG_TOGGLE_KEY: KeyCode = KeyCode::F1;
+const DEBUG_LINE_WIDTH: f32 = 2.0;
+const DEBUG_Z: f32 = 50.0;
+const DEBUG_CIRCLE_SEGMENTS: usize = 32;
+/// Points sampled along one loop of a formation's pattern.
+const DEBUG_PATTERN_SEGMENTS: usize = 96;
+const DEBUG_TARGET_RADIUS: f32 = 6.0;
+/// How far ahead velocity vectors point.
+const DEBUG_VELOCITY_SECONDS: f32 = 0.2;
+
+const HITBOX_COLOR: Color = Color::rgb(0.0, 1.0, 0.0);
+const VELOCITY_COLOR: Color = Color::rgb(1.0, 1.0, 0.0);
+const FORMATION_COLOR: Color = Color::rgb(0.0, 0.8, 1.0);
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DebugOverlay::default())
+            .add_system(debug_toggle_system)
+            .add_system(debug_overlay_system.after(debug_toggle_system));
+    }
+}
+
+#[derive(Default)]
+struct DebugOverlay {
+    enabled: bool,
+}
+
+/// One line segment of the overlay, redrawn every frame.
+#[derive(Component)]
+struct DebugLine;
+
+fn debug_toggle_system(kb: Res<Input<KeyCode>>, mut overlay: ResMut<DebugOverlay>) {
+    if kb.just_pressed(DEBUG_TOGGLE_KEY) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+fn debug_overlay_system(
+    mut commands: Commands,
+    overlay: Res<DebugOverlay>,
+    config: Res<GameConfig>,
+    lines_query: Query<Entity, With<DebugLine>>,
+    collider_query: Query<(&Transform, &SpriteSize, Option<&Hitbox>), With<CollisionLayers>>,
+    velocity_query: Query<(&Transform, &Velocity)>,
+    formation_query: Query<(&Transform, &Formation)>,
+) {
+    for entity in lines_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if !overlay.enabled {
+        return;
+    }
+
+    for (tf, size, hitbox) in collider_query.iter() {
+        let outline = match collider_shape(tf, size, hitbox) {
+            Shape::Circle { center, radius } => ellipse(center, Vec2::splat(radius)),
+            Shape::Rect { center, half_size } => vec![
+                center + Vec2::new(-half_size.x, -half_size.y),
+                center + Vec2::new(half_size.x, -half_size.y),
+                center + Vec2::new(half_size.x, half_size.y),
+                center + Vec2::new(-half_size.x, half_size.y),
+            ],
+            Shape::Polygon(points) => points,
+        };
+        spawn_loop(&mut commands, &outline, HITBOX_COLOR);
+    }
+
+    for (tf, velocity) in velocity_query.iter() {
+        let from = tf.translation.xy();
+        let to =
+            from + Vec2::new(velocity.x, velocity.y) * config.base_speed * DEBUG_VELOCITY_SECONDS;
+        spawn_line(&mut commands, from, to, VELOCITY_COLOR);
+    }
+
+    for (tf, formation) in formation_query.iter() {
+        let path: Vec<_> = (0..DEBUG_PATTERN_SEGMENTS)
+            .map(|i| {
+                let t = formation.t + i as f32 / DEBUG_PATTERN_SEGMENTS as f32;
+                formation.pattern.point(t)
+            })
+            .collect();
+        let target = formation.pattern.point(formation.t);
+
+        spawn_loop(&mut commands, &path, FORMATION_COLOR);
+        spawn_loop(
+            &mut commands,
+            &ellipse(target, Vec2::splat(DEBUG_TARGET_RADIUS)),
+            FORMATION_COLOR,
+        );
+        spawn_line(&mut commands, tf.translation.xy(), target, FORMATION_COLOR);
+    }
+}
+
+fn ellipse(center: Vec2, radius: Vec2) -> Vec<Vec2> {
+    (0..DEBUG_CIRCLE_SEGMENTS)
+        .map(|i| {
+            let angle = i as f32 * 2.0 * PI / DEBUG_CIRCLE_SEGMENTS as f32;
+            center + radius * Vec2::new(angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+fn spawn_loop(commands: &mut Commands, points: &[Vec2], color: Color) {
+    for (&from, &to) in points.iter().zip(points.iter().cycle().skip(1)) {
+        spawn_line(commands, from, to, color);
+    }
+}
+
+fn spawn_line(commands: &mut Commands, from: Vec2, to: Vec2, color: Color) {
+    let delta = to - from;
+    let center = (from + to) / 2.0;
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color,
+                custom_size: Some(Vec2::new(delta.length(), DEBUG_LINE_WIDTH)),
+                ..Default::default()
+            },
+            transform: Transform {
+                translation: center.extend(DEBUG_Z),
+                rotation: Quat::from_rotation_z(delta.y.atan2(delta.x)),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(DebugLine);
+}
@@ -0,0 +1,163 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use super::{EnemyType, EnemyTypes, Formation, Movement, SquadMember, Walk};
+use crate::{
+    animation::Animator,
+    combat::DestroyedEvent,
+    components::{EnemyKind, ExplosionToSpawn, FireRate, Health, SpriteSize},
+    config::GameConfig,
+    rng::GameRng,
+    wave::{Side, WaveState},
+    WinSize, BOSS_EXPLOSIONS,
+};
+
+/// A big enemy that changes tactics as it loses health.
+#[derive(Component, Debug)]
+pub struct Boss {
+    /// Health it started out with.
+    pub hit_points: u32,
+    /// 0 for its type's own movement and fire, then 1 on for each of its type's `phases`.
+    pub phase: usize,
+}
+
+impl Boss {
+    pub fn new(hit_points: u32) -> Self {
+        Self {
+            hit_points,
+            phase: 0,
+        }
+    }
+
+    /// Fraction of its hit points left.
+    pub fn health_left(&self, health: &Health) -> f32 {
+        health.0 as f32 / self.hit_points.max(1) as f32
+    }
+
+    /// Phase it should be in with `health` left.
+    pub fn phase_for(&self, enemy_type: &EnemyType, health: &Health) -> usize {
+        let left = self.health_left(health);
+        enemy_type
+            .phases
+            .iter()
+            .take_while(|phase| left <= phase.health)
+            .count()
+    }
+
+    /// Speed relative to the `base_speed` in the current phase.
+    pub fn speed(&self, enemy_type: &EnemyType) -> f32 {
+        match self.phase {
+            0 => enemy_type.speed,
+            phase => enemy_type.phases[phase - 1].speed,
+        }
+    }
+}
+
+/// Move bosses on to their next phase once they have lost enough health.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub(super) fn boss_phase_system(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    enemy_types: Res<EnemyTypes>,
+    wave: Res<WaveState>,
+    win_size: Res<WinSize>,
+    mut rng: ResMut<GameRng>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Boss,
+            &Health,
+            &EnemyKind,
+            &Transform,
+            &mut Animator,
+        ),
+        Changed<Health>,
+    >,
+) {
+    for (entity, mut boss, health, kind, transform, mut animator) in query.iter_mut() {
+        let enemy_type = match enemy_types.get(kind.0) {
+            Some(enemy_type) => enemy_type,
+            None => continue,
+        };
+        // Destroyed bosses are already on their way out.
+        let phase = boss.phase_for(enemy_type, health);
+        if phase <= boss.phase || health.0 == 0 {
+            continue;
+        }
+        boss.phase = phase;
+        info!("{} moves on to phase {}", enemy_type.name, phase + 1);
+
+        let next = &enemy_type.phases[phase - 1];
+        let speed = config.base_speed * next.speed * wave.difficulty(&config);
+        let mut boss_commands = commands.entity(entity);
+        boss_commands
+            .insert(next.fire)
+            .insert(FireRate(next.fire_rate))
+            .remove::<Formation>()
+            .remove::<Walk>()
+            .remove::<SquadMember>();
+        match next.movement {
+            Movement::Formation(kind) => {
+                // Come back round from the side it's nearest.
+                let side = if transform.translation.x < 0.0 {
+                    Side::Left
+                } else {
+                    Side::Right
+                };
+                let mut formation = Formation::new(&config, &win_size, kind, side, &mut *rng);
+                formation.speed = speed;
+                boss_commands.insert(formation);
+            }
+            Movement::Walk => {
+                boss_commands.insert(Walk {
+                    speed: speed.copysign(transform.scale.x),
+                });
+            }
+        }
+        animator.play(next.clip);
+    }
+}
+
+/// Set off a ring of explosions around a destroyed boss, on top of the usual one.
+pub(super) fn boss_destroyed_system(
+    mut commands: Commands,
+    mut events: EventReader<DestroyedEvent>,
+    query: Query<(&SpriteSize, &Transform), With<Boss>>,
+) {
+    for event in events.iter() {
+        let (size, transform) = match query.get(event.target.entity) {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+
+        let radius = size.0 * transform.scale.truncate().abs() / 3.0;
+        for explosion in 0..BOSS_EXPLOSIONS {
+            let angle = explosion as f32 * TAU / BOSS_EXPLOSIONS as f32;
+            let offset = Vec2::new(angle.cos(), angle.sin()) * radius;
+            commands.spawn().insert(ExplosionToSpawn(
+                event.target.translation + offset.extend(0.0),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_follow_health() {
+        let enemy_types = EnemyTypes::default();
+        let enemy_type = enemy_types.get("ninja_cat_boss").unwrap();
+        let boss = Boss::new(enemy_type.hit_points);
+        let phase = |health| boss.phase_for(enemy_type, &Health(health));
+
+        assert_eq!(phase(40), 0);
+        assert_eq!(phase(25), 0);
+        assert_eq!(phase(24), 1);
+        assert_eq!(phase(13), 1);
+        assert_eq!(phase(12), 2);
+        assert_eq!(phase(0), 2);
+    }
+}
@@ -1,65 +1,248 @@
-use bevy::prelude::Component;
-use rand::{thread_rng, Rng};
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng};
 
-use crate::{WinSize, BASE_SPEED, FORMATION_MEMBERS_MAX};
+use super::{
+    pattern::{Pattern, PatternKind},
+    squad::{Squad, SquadMember, SquadShape},
+};
+use crate::{
+    config::GameConfig,
+    wave::{Side, Spawn},
+    WinSize,
+};
 
+/// Follows its `pattern`, heading for the point at `t`.
 #[derive(Clone, Component)]
 pub struct Formation {
-    pub start: (f32, f32),
-    pub radius: (f32, f32),
-    pub pivot: (f32, f32),
+    /// Just off the screen, where members come in from.
+    pub start: Vec2,
+    pub pattern: Pattern,
+    pub t: f32,
     pub speed: f32,
-    pub angle: f32,
 }
 
+impl Formation {
+    /// A `kind` of pattern somewhere in the top half of the screen, starting off `side` of it.
+    pub fn new(
+        config: &GameConfig,
+        win_size: &WinSize,
+        kind: PatternKind,
+        side: Side,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let w_span = win_size.width / 2.0 + 100.0;
+        let h_span = win_size.height / 2.0 + 100.0;
+        let right = match side {
+            Side::Left => false,
+            Side::Right => true,
+            Side::Random => rng.gen_bool(0.5),
+        };
+        let x = if right { w_span } else { -w_span };
+        let y = rng.gen_range(-h_span..h_span);
+        let start = Vec2::new(x, y);
+
+        let (pattern, t) = Pattern::new(kind, win_size, start, rng);
+
+        Formation {
+            start,
+            pattern,
+            t,
+            speed: config.base_speed,
+        }
+    }
+}
+
+/// Starts squads and hands out places in them.
 #[derive(Default)]
 pub struct FormationMaker {
-    current_template: Option<Formation>,
+    current_squad: Option<CurrentSquad>,
     current_members: u32,
+    /// Scripted group the squad is for, if any.
+    current_group: Option<u32>,
+}
+
+/// The squad still taking on members.
+struct CurrentSquad {
+    entity: Entity,
+    kind: PatternKind,
+    shape: SquadShape,
+    start: Vec2,
 }
 
 impl FormationMaker {
-    pub fn make(&mut self, win_size: &WinSize) -> Formation {
-        match (
-            &self.current_template,
-            self.current_members >= FORMATION_MEMBERS_MAX,
-        ) {
-            (Some(template), false) => {
-                self.current_members += 1;
-                template.clone()
+    /// A place for `spawn`, going at `speed`, in a squad following a `kind` of pattern, and
+    /// where to bring it in. Every member of a group shares a squad, enemies without one
+    /// share it `formation_members_max` at a time.
+    #[allow(clippy::too_many_arguments)]
+    pub fn make(
+        &mut self,
+        commands: &mut Commands,
+        config: &GameConfig,
+        win_size: &WinSize,
+        kind: PatternKind,
+        spawn: &Spawn,
+        speed: f32,
+        rng: &mut impl Rng,
+    ) -> (SquadMember, Vec2) {
+        let full = match spawn.group {
+            Some(_) => self.current_group != spawn.group,
+            None => {
+                self.current_group.is_some() || self.current_members >= config.formation_members_max
             }
-            (None, _) | (_, true) => {
-                let mut rng = thread_rng();
+        };
+        let current = match self.current_squad.take() {
+            Some(current) if !full && current.kind == kind => current,
+            _ => {
+                let mut formation = Formation::new(config, win_size, kind, spawn.side, rng);
+                formation.speed = speed;
+                let shape = spawn
+                    .squad
+                    .unwrap_or_else(|| *SquadShape::ALL.choose(rng).unwrap());
+                let start = formation.start;
+                let entity = commands
+                    .spawn()
+                    .insert(Transform::from_translation(start.extend(0.0)))
+                    .insert(formation)
+                    .insert(Squad::new(shape))
+                    .id();
+                self.current_members = 0;
+                self.current_group = spawn.group;
 
-                let w_span = win_size.width / 2.0 + 100.0;
-                let h_span = win_size.height / 2.0 + 100.0;
-                let x = if rng.gen_bool(0.5) { w_span } else { -w_span };
-                let y = rng.gen_range(-h_span..h_span) as f32;
-                let start = (x, y);
+                CurrentSquad {
+                    entity,
+                    kind,
+                    shape,
+                    start,
+                }
+            }
+        };
 
-                let w_span = win_size.width / 4.0;
-                let h_span = win_size.height / 3.0 + 50.0;
-                let pivot = (rng.gen_range(-w_span..w_span), rng.gen_range(0.0..h_span));
+        let slot = self.current_members as usize;
+        self.current_members += 1;
+        let member = SquadMember {
+            squad: current.entity,
+            slot,
+            speed,
+        };
+        let start = current.start + current.shape.offset(slot, slot + 1);
+        self.current_squad = Some(current);
 
-                let radius = (rng.gen_range(80.0..150.0), 100.0);
+        (member, start)
+    }
 
-                let angle = (y - pivot.1).atan2(x - pivot.0);
+    /// Stop filling `squad`, now that it's gone.
+    pub fn forget(&mut self, squad: Entity) {
+        if self
+            .current_squad
+            .as_ref()
+            .is_some_and(|current| current.entity == squad)
+        {
+            self.current_squad = None;
+        }
+    }
+}
 
-                let speed = BASE_SPEED;
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::CommandQueue;
 
-                let formation = Formation {
-                    start,
-                    radius,
-                    pivot,
-                    speed,
-                    angle,
-                };
+    use super::*;
+    use crate::rng::GameRng;
 
-                self.current_template = Some(formation.clone());
-                self.current_members = 1;
+    const WIN_SIZE: WinSize = WinSize {
+        width: 1280.0,
+        height: 720.0,
+    };
 
-                formation
-            }
+    fn spawn(side: Side, group: Option<u32>) -> Spawn {
+        Spawn {
+            enemy: "ninja_cat",
+            movement: None,
+            side,
+            fire_rate: None,
+            group,
+            squad: None,
+            tick: 0,
         }
     }
+
+    fn places(seed: u64) -> Vec<(Entity, usize, Vec2)> {
+        let world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let mut maker = FormationMaker::default();
+        let mut rng = GameRng::new(seed);
+
+        (0..10)
+            .map(|i| {
+                let (member, start) = maker.make(
+                    &mut commands,
+                    &GameConfig::default(),
+                    &WIN_SIZE,
+                    PatternKind::ALL[i / 2 % PatternKind::ALL.len()],
+                    &spawn(Side::Random, None),
+                    100.0,
+                    &mut rng,
+                );
+                (member.squad, member.slot, start)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_squads() {
+        assert_eq!(places(7), places(7));
+        assert_ne!(places(7), places(8));
+    }
+
+    #[test]
+    fn groups_share_a_squad() {
+        let config = GameConfig::default();
+        let world = World::new();
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &world);
+        let mut maker = FormationMaker::default();
+        let mut rng = GameRng::new(1);
+        let mut join = |maker: &mut FormationMaker, kind, side, group| {
+            let (member, start) = maker.make(
+                &mut commands,
+                &config,
+                &WIN_SIZE,
+                kind,
+                &spawn(side, group),
+                100.0,
+                &mut rng,
+            );
+            assert_eq!(start.x < 0.0, side == Side::Left);
+            (member.squad, member.slot)
+        };
+
+        // More members than `formation_members_max`, all in the one squad.
+        let (first, _) = join(&mut maker, PatternKind::Orbit, Side::Left, Some(0));
+        for slot in 1..4 {
+            assert_eq!(
+                join(&mut maker, PatternKind::Orbit, Side::Left, Some(0)),
+                (first, slot)
+            );
+        }
+        let (second, slot) = join(&mut maker, PatternKind::Sine, Side::Right, Some(1));
+        assert_ne!(second, first);
+        assert_eq!(slot, 0);
+        let (third, _) = join(&mut maker, PatternKind::Sine, Side::Right, None);
+        assert_ne!(third, second);
+
+        // Enemies without a group only share a squad following the same pattern.
+        assert_ne!(
+            join(&mut maker, PatternKind::Strafe, Side::Right, None).0,
+            third
+        );
+
+        // Nobody joins a squad that's gone.
+        let (fourth, _) = join(&mut maker, PatternKind::Strafe, Side::Right, None);
+        maker.forget(fourth);
+        assert_ne!(
+            join(&mut maker, PatternKind::Strafe, Side::Right, None).0,
+            fourth
+        );
+    }
 }
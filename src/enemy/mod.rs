@@ -1,123 +1,323 @@
-use std::f32::consts::PI;
-
 use crate::{
-    components::{Animate, Enemy, Fire, FromEnemy, Movable, OnOutsideWindow, SpriteSize, Velocity},
-    EnemyCount, GameTextures, WinSize, ENEMY_FIRE_SIZE, ENEMY_MAX, ENEMY_SIZE, SPRITE_SCALE,
-    TIME_STEP,
+    collision::{CollisionLayer, CollisionLayers, Hitbox},
+    combat::DamageLabel,
+    components::{
+        Damage, Enemy, EnemyKind, Fire, FireRate, Health, Movable, OnOutsideWindow, Player,
+        SpawnTick, SpriteSize, Velocity,
+    },
+    config::GameConfig,
+    rng::GameRng,
+    simulation::{every, SimulationClock, SimulationStage},
+    wave::{Side, WaveLabel, WaveState},
+    EnemyCount, GameState, GameTextures, WinSize, ENEMY_FIRE_INSET, TIME_STEP,
 };
-use bevy::{core::FixedTimestep, ecs::schedule::ShouldRun, prelude::*};
-use rand::{thread_rng, Rng};
+use bevy::prelude::*;
+use rand::Rng;
 
-use self::formation::{Formation, FormationMaker};
+pub use self::{
+    boss::Boss,
+    formation::Formation,
+    pattern::{Pattern, PatternKind},
+    squad::{squad_member_system, Squad, SquadMember, SquadShape},
+    types::{BossPhase, EnemyType, EnemyTypes, FirePattern, Movement},
+};
+use self::{
+    boss::{boss_destroyed_system, boss_phase_system},
+    formation::FormationMaker,
+    squad::squad_system,
+};
 
+mod boss;
 mod formation;
+mod pattern;
+mod squad;
+mod types;
+
+/// Angle between the shots of a `FirePattern::Spread`, in radians.
+const SPREAD_ANGLE: f32 = 0.3;
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(FormationMaker::default())
-            .add_system_set(
+            .init_resource::<EnemyTypes>()
+            .add_system_set_to_stage(
+                SimulationStage,
                 SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(1.0))
-                    .with_system(enemy_spawn_system),
+                    .with_run_criteria(every(1.0))
+                    .with_system(enemy_spawn_system.after(WaveLabel)),
             )
-            .add_system_set(
+            .add_system_to_stage(SimulationStage, enemy_fire_system)
+            .add_system_to_stage(SimulationStage, enemy_movement_system)
+            .add_system_to_stage(SimulationStage, squad_system)
+            .add_system_to_stage(
+                SimulationStage,
+                squad_member_system.after(enemy_movement_system),
+            )
+            .add_system_to_stage(SimulationStage, enemy_walk_system)
+            .add_system_set_to_stage(
+                SimulationStage,
                 SystemSet::new()
-                    .with_run_criteria(enemy_fire_criteria)
-                    .with_system(enemy_fire_system),
+                    .after(DamageLabel)
+                    .with_system(boss_phase_system)
+                    .with_system(boss_destroyed_system),
             )
-            .add_system(enemy_movement_system);
+            .add_system(enemy_config_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(enemy_reset_system),
+            );
+    }
+}
+
+/// Walks back and forth across the screen, at `speed` pixels per second to the right.
+#[derive(Component)]
+pub struct Walk {
+    pub speed: f32,
+}
+
+fn enemy_reset_system(mut formation_maker: ResMut<FormationMaker>) {
+    *formation_maker = FormationMaker::default();
+}
+
+/// Bring the enemies already out up to date with config changes.
+#[allow(clippy::type_complexity)]
+fn enemy_config_system(
+    config: Res<GameConfig>,
+    enemy_types: Res<EnemyTypes>,
+    wave: Res<WaveState>,
+    mut query: Query<(
+        &EnemyKind,
+        Option<&Boss>,
+        Option<&mut Formation>,
+        Option<&mut SquadMember>,
+        Option<&mut Walk>,
+    )>,
+) {
+    if config.is_changed() {
+        for (kind, boss, formation, member, walk) in query.iter_mut() {
+            let speed = enemy_types
+                .get(kind.0)
+                .map_or(1.0, |t| boss.map_or(t.speed, |boss| boss.speed(t)))
+                * config.base_speed
+                * wave.difficulty(&config);
+            if let Some(mut formation) = formation {
+                formation.speed = speed;
+            }
+            // Their squad keeps up with the slowest of them.
+            if let Some(mut member) = member {
+                member.speed = speed;
+            }
+            if let Some(mut walk) = walk {
+                walk.speed = speed.copysign(walk.speed);
+            }
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn enemy_spawn_system(
     mut commands: Commands,
     game_textures: Res<GameTextures>,
+    enemy_types: Res<EnemyTypes>,
+    mut wave: ResMut<WaveState>,
     mut enemy_count: ResMut<EnemyCount>,
     mut formation_maker: ResMut<FormationMaker>,
+    mut rng: ResMut<GameRng>,
+    clock: Res<SimulationClock>,
+    config: Res<GameConfig>,
     win_size: Res<WinSize>,
 ) {
-    if enemy_count.0 < ENEMY_MAX {
-        let formation = formation_maker.make(&win_size);
-        let (x, y) = formation.start;
-
-        commands
-            .spawn_bundle(SpriteSheetBundle {
-                texture_atlas: game_textures.enemy.clone(),
-                transform: Transform {
-                    translation: Vec3::new(x, y, 10.0),
-                    scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.0),
-                    ..Default::default()
-                },
-                ..Default::default()
-            })
-            .insert(Enemy)
-            .insert(formation)
-            .insert(SpriteSize::from(ENEMY_SIZE))
-            .insert(Animate {
-                range: 0..=7,
-                ..Default::default()
+    let spawn = match wave.spawn(&config, &enemy_count, clock.tick) {
+        Some(spawn) => spawn,
+        None => return,
+    };
+    let enemy_type = match enemy_types.get(spawn.enemy) {
+        Some(enemy_type) => enemy_type,
+        None => return,
+    };
+    let sheet = game_textures.enemy(enemy_type.sheet);
+    let scale = config.sprite_scale * enemy_type.scale;
+    let speed = config.base_speed * enemy_type.speed * wave.difficulty(&config);
+
+    let entity = commands
+        .spawn_bundle(SpriteSheetBundle {
+            texture_atlas: sheet.atlas.clone(),
+            ..Default::default()
+        })
+        .id();
+
+    let (x, y) = match spawn.movement.unwrap_or(enemy_type.movement) {
+        Movement::Formation(kind) => {
+            let (member, start) = formation_maker.make(
+                &mut commands,
+                &config,
+                &win_size,
+                kind,
+                &spawn,
+                speed,
+                &mut *rng,
+            );
+            commands.entity(entity).insert(member);
+            (start.x, start.y)
+        }
+        Movement::Walk => {
+            // From just off one side, somewhere in the top half.
+            let w_span = win_size.width / 2.0 + sheet.tile_size.x * scale;
+            let h_span = win_size.height / 2.0 - sheet.tile_size.y * scale;
+            let left = match spawn.side {
+                Side::Left => true,
+                Side::Right => false,
+                Side::Random => rng.gen_bool(0.5),
+            };
+            let x = if left { -w_span } else { w_span };
+            let y = rng.gen_range(0.0..h_span.max(1.0));
+            commands.entity(entity).insert(Walk {
+                speed: if left { speed } else { -speed },
             });
+            (x, y)
+        }
+    };
 
-        enemy_count.0 += 1;
+    let mut enemy = commands.entity(entity);
+    enemy
+        .insert(Transform {
+            translation: Vec3::new(x, y, 10.0),
+            scale: Vec3::new(scale, scale, 1.0),
+            ..Default::default()
+        })
+        .insert(Enemy)
+        .insert(EnemyKind(enemy_type.name))
+        .insert(SpawnTick(clock.tick))
+        .insert(Health(enemy_type.hit_points))
+        .insert(enemy_type.fire)
+        .insert(SpriteSize(sheet.tile_size))
+        .insert(CollisionLayers::new(CollisionLayer::Enemy, &[]))
+        .insert(sheet.animator(enemy_type.clip));
+    if let Some(hitbox) = &enemy_type.hitbox {
+        enemy.insert(hitbox.clone());
+    }
+    if let Some(fire_rate) = spawn.fire_rate {
+        enemy.insert(FireRate(fire_rate));
     }
+    if enemy_type.is_boss() {
+        enemy.insert(Boss::new(enemy_type.hit_points));
+    }
+
+    enemy_count.0 += 1;
 }
 
-fn enemy_fire_criteria() -> ShouldRun {
-    if thread_rng().gen_bool(1.0 / 120.0) {
-        ShouldRun::Yes
-    } else {
-        ShouldRun::No
+/// Directions of the shots fired in `pattern` from `from`, as velocities.
+fn fire_directions(pattern: FirePattern, from: Vec2, player: Option<Vec2>) -> Vec<Vec2> {
+    match pattern {
+        FirePattern::Single => vec![-Vec2::Y],
+        FirePattern::Aimed => {
+            let aim = player.map_or(-Vec2::Y, |player| player - from);
+            vec![aim.try_normalize().unwrap_or(-Vec2::Y)]
+        }
+        FirePattern::Spread(count) => (0..count)
+            .map(|shot| {
+                let angle = (shot as f32 - (count - 1) as f32 / 2.0) * SPREAD_ANGLE;
+                Vec2::new(angle.sin(), -angle.cos())
+            })
+            .collect(),
     }
 }
 
 fn enemy_fire_system(
     mut commands: Commands,
+    config: Res<GameConfig>,
     game_textures: Res<GameTextures>,
-    enemy_query: Query<&Transform, With<Enemy>>,
+    wave: Res<WaveState>,
+    mut rng: ResMut<GameRng>,
+    enemy_query: Query<(&SpawnTick, &Transform, &FirePattern, Option<&FireRate>), With<Enemy>>,
+    player_query: Query<&Transform, With<Player>>,
 ) {
-    for &tf in enemy_query.iter() {
+    let sheet = &game_textures.enemy_fire;
+    let scale = config.sprite_scale;
+    let player = player_query
+        .get_single()
+        .ok()
+        .map(|tf| tf.translation.truncate());
+
+    // One roll each, in an order the simulation owns.
+    let mut enemies: Vec<_> = enemy_query.iter().collect();
+    enemies.sort_unstable_by_key(|(spawn_tick, ..)| **spawn_tick);
+    for (_, &tf, &pattern, fire_rate) in enemies {
+        let chance = fire_rate.map_or(config.enemy_fire_chance(), |rate| rate.0 * TIME_STEP as f64)
+            * wave.difficulty(&config) as f64;
+        if !rng.gen_bool(chance.min(1.0)) {
+            continue;
+        }
+
         let (x, y) = (tf.translation.x, tf.translation.y);
 
-        commands
-            .spawn_bundle(SpriteSheetBundle {
-                texture_atlas: game_textures.enemy_fire.clone(),
-                transform: Transform {
-                    translation: Vec3::new(x, y, 10.0),
-                    scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.0),
+        for direction in fire_directions(pattern, Vec2::new(x, y), player) {
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas: sheet.atlas.clone(),
+                    transform: Transform {
+                        translation: Vec3::new(x, y, 10.0),
+                        scale: Vec3::new(scale, scale, 1.0),
+                        ..Default::default()
+                    },
                     ..Default::default()
-                },
-                ..Default::default()
-            })
-            .insert(Fire)
-            .insert(FromEnemy)
-            .insert(SpriteSize::from(ENEMY_FIRE_SIZE))
-            .insert(Movable {
-                on_outside_window: OnOutsideWindow::Despawn,
-            })
-            .insert(Velocity { x: 0.0, y: -1.0 })
-            .insert(Animate {
-                range: 0..=1,
-                ..Default::default()
-            });
+                })
+                .insert(Fire)
+                .insert(Damage(1))
+                .insert(SpriteSize(sheet.tile_size))
+                .insert(Hitbox::inset(sheet.tile_size.into(), ENEMY_FIRE_INSET))
+                .insert(CollisionLayers::new(
+                    CollisionLayer::EnemyFire,
+                    &[CollisionLayer::Player],
+                ))
+                .insert(Movable {
+                    on_outside_window: OnOutsideWindow::Despawn,
+                })
+                .insert(Velocity {
+                    x: direction.x,
+                    y: direction.y,
+                })
+                .insert(sheet.animator("flap"));
+        }
     }
 }
 
-fn enemy_movement_system(mut query: Query<(&mut Transform, &mut Formation), With<Enemy>>) {
+pub fn enemy_walk_system(
+    win_size: Res<WinSize>,
+    mut query: Query<(&mut Transform, &mut Walk, &SpriteSize), With<Enemy>>,
+) {
+    for (mut transform, mut walk, size) in query.iter_mut() {
+        let half_width = size.0.x * transform.scale.x.abs() / 2.0;
+        let edge = win_size.width / 2.0 - half_width;
+        let x = transform.translation.x;
+        if (x > edge && walk.speed > 0.0) || (x < -edge && walk.speed < 0.0) {
+            walk.speed = -walk.speed;
+        }
+
+        transform.translation.x += walk.speed * TIME_STEP;
+        transform.scale.x = transform.scale.x.abs().copysign(walk.speed);
+    }
+}
+
+/// Move enemies and squads along their formation's pattern.
+pub fn enemy_movement_system(
+    player_query: Query<&Transform, (With<Player>, Without<Formation>)>,
+    mut query: Query<(&mut Transform, &mut Formation)>,
+) {
+    let player = player_query.iter().next().map(|t| t.translation.truncate());
     for (mut transform, mut formation) in query.iter_mut() {
+        let formation = &mut *formation;
+        if let Some(player) = player {
+            formation.pattern.aim(formation.t, player);
+        }
+
         let (x_org, y_org) = (transform.translation.x, transform.translation.y);
         let max_distance = TIME_STEP * formation.speed;
 
-        // 1 for counter clockwise, -1 for clockwise
-        let dir: f32 = if formation.start.0 < 0.0 { 1.0 } else { -1.0 };
-        let (x_pivot, y_pivot) = formation.pivot;
-        let (x_radius, y_radius) = formation.radius;
-        let angle = formation.angle
-            + dir * formation.speed * TIME_STEP / (x_radius.min(y_radius) * PI / 2.0);
-
-        let x_dst = x_radius * angle.cos() + x_pivot;
-        let y_dst = y_radius * angle.sin() + y_pivot;
+        let t = formation.pattern.advance(formation.t, max_distance);
+        let dst = formation.pattern.point(t);
+        let (x_dst, y_dst) = (dst.x, dst.y);
 
         let dx = x_org - x_dst;
         let dy = y_org - y_dst;
@@ -134,13 +334,15 @@ fn enemy_movement_system(mut query: Query<(&mut Transform, &mut Formation), With
         let y = if dy > 0.0 { y.max(y_dst) } else { y.min(y_dst) };
 
         if distance < max_distance * formation.speed / 20.0 {
-            formation.angle = angle;
+            formation.t = t;
         }
 
-        transform.scale.x = match transform.translation.x > x {
-            true => -1.0 * SPRITE_SCALE,
-            false => 1.0 * SPRITE_SCALE,
+        let facing = if transform.translation.x > x {
+            -1.0
+        } else {
+            1.0
         };
+        transform.scale.x = transform.scale.x.abs() * facing;
 
         let translation = &mut transform.translation;
         (translation.x, translation.y) = (x, y);
@@ -0,0 +1,392 @@
+use std::f32::consts::TAU;
+
+use bevy::math::Vec2;
+use rand::Rng;
+
+use crate::WinSize;
+
+/// Steps in `t` used to measure how fast a pattern goes.
+const PATTERN_STEP: f32 = 0.001;
+/// Most `t` moves in one go, so slow parts of a path don't send it flying.
+const PATTERN_MAX_ADVANCE: f32 = 0.05;
+/// Times an advance gets cut back to fit.
+const PATTERN_REFINE: usize = 3;
+/// Part of each loop of a `Pattern::DiveBomb` spent hovering, then diving.
+const DIVE_HOVER: f32 = 0.4;
+const DIVE_DIVE: f32 = 0.7;
+const DIVE_HOVER_RADIUS: f32 = 20.0;
+
+/// The kinds of `Pattern`, as named in wave scripts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternKind {
+    Orbit,
+    Sine,
+    FigureEight,
+    DiveBomb,
+    Bezier,
+    Strafe,
+}
+
+impl PatternKind {
+    pub const ALL: [PatternKind; 6] = [
+        PatternKind::Orbit,
+        PatternKind::Sine,
+        PatternKind::FigureEight,
+        PatternKind::DiveBomb,
+        PatternKind::Bezier,
+        PatternKind::Strafe,
+    ];
+
+    /// Snake case name.
+    pub fn name(self) -> &'static str {
+        match self {
+            PatternKind::Orbit => "orbit",
+            PatternKind::Sine => "sine",
+            PatternKind::FigureEight => "figure_eight",
+            PatternKind::DiveBomb => "dive_bomb",
+            PatternKind::Bezier => "bezier",
+            PatternKind::Strafe => "strafe",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+/// A path for a formation to follow, as a point for every `t`. Each time `t` goes up by 1
+/// the path goes round once, or across the screen and back, and it never jumps.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// Round an ellipse.
+    Orbit {
+        pivot: Vec2,
+        radius: Vec2,
+        clockwise: bool,
+    },
+    /// From `from` to `to` and back, weaving `amplitude` either side of the line between.
+    Sine {
+        from: Vec2,
+        to: Vec2,
+        amplitude: f32,
+        /// Times it weaves each way.
+        waves: f32,
+    },
+    /// A sideways figure eight, `size` either side of `center`.
+    FigureEight { center: Vec2, size: Vec2 },
+    /// Hover around `home`, then dive through `target` and swing back round.
+    DiveBomb { home: Vec2, target: Vec2 },
+    /// Round smooth cubic Bezier curves through every one of `points`.
+    Bezier { points: Vec<Vec2> },
+    /// Straight from `from` to `to` and back.
+    Strafe { from: Vec2, to: Vec2 },
+}
+
+impl Pattern {
+    /// A random pattern of the given kind around the top of the screen, for a formation
+    /// coming in from `start`. Returns the `t` to head for first too.
+    pub fn new(
+        kind: PatternKind,
+        win_size: &WinSize,
+        start: Vec2,
+        rng: &mut impl Rng,
+    ) -> (Self, f32) {
+        let (w, h) = (win_size.width / 2.0, win_size.height / 2.0);
+        let right = start.x > 0.0;
+        let side = if right { 1.0 } else { -1.0 };
+
+        match kind {
+            PatternKind::Orbit => {
+                let w_span = win_size.width / 4.0;
+                let h_span = win_size.height / 3.0 + 50.0;
+                let pivot = Vec2::new(rng.gen_range(-w_span..w_span), rng.gen_range(0.0..h_span));
+                let radius = Vec2::new(rng.gen_range(80.0..150.0), 100.0);
+                // Counter clockwise from the left, joining where it comes in.
+                let angle = (start.y - pivot.y).atan2(start.x - pivot.x);
+                let pattern = Pattern::Orbit {
+                    pivot,
+                    radius,
+                    clockwise: right,
+                };
+                (pattern, if right { -angle } else { angle } / TAU)
+            }
+            PatternKind::Sine => {
+                let y = rng.gen_range(0.0..h * 0.6);
+                let pattern = Pattern::Sine {
+                    from: Vec2::new(side * (w - 100.0), y),
+                    to: Vec2::new(-side * (w - 100.0), y),
+                    amplitude: rng.gen_range(40.0..100.0),
+                    waves: rng.gen_range(1.5..3.0),
+                };
+                (pattern, 0.0)
+            }
+            PatternKind::FigureEight => {
+                let center = Vec2::new(
+                    rng.gen_range(-w / 2.0..w / 2.0),
+                    rng.gen_range(h * 0.2..h * 0.5),
+                );
+                let size = Vec2::new(rng.gen_range(150.0..250.0), rng.gen_range(80.0..120.0));
+                // Going out towards that side first.
+                let pattern = Pattern::FigureEight { center, size };
+                (pattern, if right { 0.0 } else { 0.5 })
+            }
+            PatternKind::DiveBomb => {
+                let home = Vec2::new(
+                    side * rng.gen_range(0.0..w * 0.6),
+                    rng.gen_range(h * 0.4..h - 80.0),
+                );
+                let target = Vec2::new(home.x, -h + 80.0);
+                (Pattern::DiveBomb { home, target }, 0.0)
+            }
+            PatternKind::Bezier => {
+                let points = (0..rng.gen_range(3..6))
+                    .map(|_| {
+                        Vec2::new(
+                            rng.gen_range(-w + 100.0..w - 100.0),
+                            rng.gen_range(-h / 3.0..h - 80.0),
+                        )
+                    })
+                    .collect();
+                (Pattern::Bezier { points }, 0.0)
+            }
+            PatternKind::Strafe => {
+                let y = rng.gen_range(h * 0.2..h - 80.0);
+                let pattern = Pattern::Strafe {
+                    from: Vec2::new(side * (w - 80.0), y),
+                    to: Vec2::new(-side * (w - 80.0), y),
+                };
+                (pattern, 0.0)
+            }
+        }
+    }
+
+    pub fn kind(&self) -> PatternKind {
+        match self {
+            Pattern::Orbit { .. } => PatternKind::Orbit,
+            Pattern::Sine { .. } => PatternKind::Sine,
+            Pattern::FigureEight { .. } => PatternKind::FigureEight,
+            Pattern::DiveBomb { .. } => PatternKind::DiveBomb,
+            Pattern::Bezier { .. } => PatternKind::Bezier,
+            Pattern::Strafe { .. } => PatternKind::Strafe,
+        }
+    }
+
+    /// Where the path is at `t`.
+    pub fn point(&self, t: f32) -> Vec2 {
+        let loop_t = t.rem_euclid(1.0);
+        // 0 to 1 and back again.
+        let there_and_back = 1.0 - (1.0 - 2.0 * loop_t).abs();
+
+        match self {
+            Pattern::Orbit {
+                pivot,
+                radius,
+                clockwise,
+            } => {
+                let angle = if *clockwise { -TAU * t } else { TAU * t };
+                *pivot + *radius * Vec2::new(angle.cos(), angle.sin())
+            }
+            Pattern::Sine {
+                from,
+                to,
+                amplitude,
+                waves,
+            } => {
+                let across = *to - *from;
+                let normal = across.perp().normalize_or_zero();
+                let weave = (TAU * waves * there_and_back).sin();
+                *from + across * there_and_back + normal * *amplitude * weave
+            }
+            Pattern::FigureEight { center, size } => {
+                let angle = TAU * t;
+                *center + *size * Vec2::new(angle.cos(), (2.0 * angle).sin() / 2.0)
+            }
+            Pattern::DiveBomb { home, target } => {
+                let swing = if home.x > 0.0 { -200.0 } else { 200.0 };
+                if loop_t < DIVE_HOVER {
+                    let angle = TAU * loop_t / DIVE_HOVER;
+                    *home + DIVE_HOVER_RADIUS * Vec2::new(angle.sin(), angle.cos() - 1.0)
+                } else if loop_t < DIVE_DIVE {
+                    let drop = (home.y - target.y).abs() / 2.0;
+                    cubic_bezier(
+                        [
+                            *home,
+                            *home + Vec2::new(-swing / 2.0, drop / 2.0),
+                            *target + Vec2::new(0.0, drop),
+                            *target,
+                        ],
+                        (loop_t - DIVE_HOVER) / (DIVE_DIVE - DIVE_HOVER),
+                    )
+                } else {
+                    cubic_bezier(
+                        [
+                            *target,
+                            *target + Vec2::new(swing, -150.0),
+                            *home + Vec2::new(swing, -150.0),
+                            *home,
+                        ],
+                        (loop_t - DIVE_DIVE) / (1.0 - DIVE_DIVE),
+                    )
+                }
+            }
+            Pattern::Bezier { points } => {
+                let count = points.len();
+                if count < 2 {
+                    return points.first().copied().unwrap_or_default();
+                }
+                // Catmull-Rom tangents, so every curve carries on smoothly from the last.
+                let along = loop_t * count as f32;
+                let segment = (along as usize).min(count - 1);
+                let point = |offset: usize| points[(segment + offset + count - 1) % count];
+                let (before, from, to, after) = (point(0), point(1), point(2), point(3));
+                cubic_bezier(
+                    [
+                        from,
+                        from + (to - before) / 6.0,
+                        to - (after - from) / 6.0,
+                        to,
+                    ],
+                    along - segment as f32,
+                )
+            }
+            Pattern::Strafe { from, to } => from.lerp(*to, there_and_back),
+        }
+    }
+
+    /// `t` about `distance` further along the path from `t`.
+    pub fn advance(&self, t: f32, distance: f32) -> f32 {
+        // Either side, so turning round doesn't look like stopping.
+        let point = self.point(t);
+        let speed = self
+            .point(t + PATTERN_STEP)
+            .distance(point)
+            .max(self.point(t - PATTERN_STEP).distance(point))
+            / PATTERN_STEP;
+        let mut step = if speed > 0.0 {
+            (distance / speed).min(PATTERN_MAX_ADVANCE)
+        } else {
+            PATTERN_MAX_ADVANCE
+        };
+        // Cut back when it runs into a faster part of the path.
+        for _ in 0..PATTERN_REFINE {
+            let moved = self.point(t + step).distance(point);
+            if moved <= distance {
+                break;
+            }
+            step *= distance / moved;
+        }
+        t + step
+    }
+
+    /// Aim a dive bomb at `at`, unless it's already on its way down. The path at `t` doesn't
+    /// change.
+    pub fn aim(&mut self, t: f32, at: Vec2) {
+        if let Pattern::DiveBomb { target, .. } = self {
+            if t.rem_euclid(1.0) < DIVE_HOVER {
+                *target = at;
+            }
+        }
+    }
+}
+
+fn cubic_bezier(points: [Vec2; 4], t: f32) -> Vec2 {
+    let u = 1.0 - t;
+    points[0] * u * u * u
+        + points[1] * 3.0 * u * u * t
+        + points[2] * 3.0 * u * t * t
+        + points[3] * t * t * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::GameRng;
+
+    const WIN_SIZE: WinSize = WinSize {
+        width: 1280.0,
+        height: 720.0,
+    };
+
+    fn patterns() -> Vec<Pattern> {
+        let mut rng = GameRng::new(1);
+        let mut patterns = Vec::new();
+        for kind in PatternKind::ALL {
+            for x in [-800.0, 800.0] {
+                patterns.push(Pattern::new(kind, &WIN_SIZE, Vec2::new(x, 0.0), &mut rng).0);
+            }
+        }
+        patterns
+    }
+
+    #[test]
+    fn names() {
+        for kind in PatternKind::ALL {
+            assert_eq!(PatternKind::from_name(kind.name()), Some(kind));
+        }
+        assert_eq!(PatternKind::from_name("loop"), None);
+    }
+
+    #[test]
+    fn paths_never_jump() {
+        for pattern in patterns() {
+            let mut previous = pattern.point(-1.0);
+            for step in 1..=3000 {
+                let t = -1.0 + step as f32 * PATTERN_STEP;
+                let point = pattern.point(t);
+                assert!(
+                    point.distance(previous) < 10.0,
+                    "{:?} jumps from {} to {} at {}",
+                    pattern.kind(),
+                    previous,
+                    point,
+                    t
+                );
+                previous = point;
+            }
+        }
+    }
+
+    #[test]
+    fn paths_loop_back_round() {
+        for pattern in patterns() {
+            for t in [0.0, 0.25, 0.7] {
+                assert!(
+                    pattern.point(t).distance(pattern.point(t + 1.0)) < 0.1,
+                    "{:?} at {}",
+                    pattern.kind(),
+                    t
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn advancing_moves_about_that_far() {
+        for pattern in patterns() {
+            let mut t = 0.0;
+            for _ in 0..500 {
+                let next = pattern.advance(t, 5.0);
+                let moved = pattern.point(t).distance(pattern.point(next));
+                assert!(moved < 7.5, "{:?} moved {} at {}", pattern.kind(), moved, t);
+                assert!(next > t);
+                t = next;
+            }
+        }
+    }
+
+    #[test]
+    fn dive_bombs_aim_while_hovering() {
+        let start = Vec2::new(800.0, 0.0);
+        let mut rng = GameRng::new(1);
+        let mut pattern = Pattern::new(PatternKind::DiveBomb, &WIN_SIZE, start, &mut rng).0;
+        let player = Vec2::new(-300.0, -300.0);
+
+        pattern.aim(0.5, player);
+        assert_ne!(pattern.point(DIVE_DIVE), player);
+
+        let hovering = pattern.point(0.2);
+        pattern.aim(0.2, player);
+        assert_eq!(pattern.point(0.2), hovering);
+        assert!(pattern.point(DIVE_DIVE).distance(player) < 0.01);
+    }
+}
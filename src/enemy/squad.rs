@@ -0,0 +1,242 @@
+use std::{
+    collections::BTreeMap,
+    f32::consts::{PI, TAU},
+};
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use super::{formation::FormationMaker, Formation, PatternKind};
+use crate::{
+    components::SpawnTick, config::GameConfig, rng::GameRng, wave::Side, WinSize, SQUAD_CATCH_UP,
+    SQUAD_GRID_COLUMNS, SQUAD_SCATTER, SQUAD_SPACING, TIME_STEP,
+};
+
+/// How the members of a squad line up around it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SquadShape {
+    /// Pointing down, the first member at the tip.
+    V,
+    /// Side by side.
+    Line,
+    Circle,
+    /// Rows of `SQUAD_GRID_COLUMNS`.
+    Grid,
+}
+
+impl SquadShape {
+    pub const ALL: [SquadShape; 4] = [
+        SquadShape::V,
+        SquadShape::Line,
+        SquadShape::Circle,
+        SquadShape::Grid,
+    ];
+
+    /// Where member `slot` of `count` goes, relative to the squad.
+    pub fn offset(self, slot: usize, count: usize) -> Vec2 {
+        let count = count.max(slot + 1);
+        // The first in the middle, then the rest to the right and left of it in turn.
+        let rank = slot.div_ceil(2) as f32;
+        let side = if slot % 2 == 1 { -1.0 } else { 1.0 };
+
+        match self {
+            SquadShape::V => Vec2::new(side * rank, rank) * SQUAD_SPACING,
+            SquadShape::Line => Vec2::new(side * rank, 0.0) * SQUAD_SPACING,
+            SquadShape::Circle if count == 1 => Vec2::ZERO,
+            SquadShape::Circle => {
+                // Just big enough for neighbours to be `SQUAD_SPACING` apart.
+                let radius = SQUAD_SPACING / (2.0 * (PI / count as f32).sin());
+                let angle = TAU * slot as f32 / count as f32;
+                radius * Vec2::new(angle.sin(), angle.cos())
+            }
+            SquadShape::Grid => {
+                let columns = SQUAD_GRID_COLUMNS.min(count);
+                let (row, column) = (slot / columns, slot % columns);
+                let x = column as f32 - (columns - 1) as f32 / 2.0;
+                Vec2::new(x, row as f32) * SQUAD_SPACING
+            }
+        }
+    }
+}
+
+/// Enemies moving together, lined up around where the squad is. Follows its `Formation`
+/// at the pace of its slowest member.
+#[derive(Component, Debug)]
+pub struct Squad {
+    pub shape: SquadShape,
+    /// Members still in it.
+    pub members: usize,
+    /// Most members it has had at once.
+    pub most: usize,
+}
+
+impl Squad {
+    pub fn new(shape: SquadShape) -> Self {
+        Self {
+            shape,
+            members: 0,
+            most: 0,
+        }
+    }
+}
+
+/// An enemy keeping its place in a squad.
+#[derive(Component, Clone, Debug)]
+pub struct SquadMember {
+    pub squad: Entity,
+    /// Place in the squad's shape. Closed up when members are lost.
+    pub slot: usize,
+    /// Its own speed, in pixels per second.
+    pub speed: f32,
+}
+
+/// Close up the ranks of squads that have lost members, and scatter the ones that have
+/// lost too many.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn squad_system(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    win_size: Res<WinSize>,
+    mut rng: ResMut<GameRng>,
+    mut formation_maker: ResMut<FormationMaker>,
+    mut squad_query: Query<(Entity, &mut Squad, &mut Formation)>,
+    mut member_query: Query<(Entity, &mut SquadMember, &SpawnTick, &Transform), Without<Squad>>,
+) {
+    let mut members: BTreeMap<Entity, Vec<(usize, SpawnTick, Entity)>> = BTreeMap::new();
+    for (entity, member, &spawn_tick, _) in member_query.iter() {
+        members
+            .entry(member.squad)
+            .or_default()
+            .push((member.slot, spawn_tick, entity));
+    }
+    let mut scattered = Vec::new();
+
+    for (squad_entity, mut squad, mut formation) in squad_query.iter_mut() {
+        let mut squad_members = members.remove(&squad_entity).unwrap_or_default();
+        squad_members.sort_unstable();
+        let count = squad_members.len();
+        squad.most = squad.most.max(count);
+
+        if count == 0 || (count as f32) < squad.most as f32 * SQUAD_SCATTER {
+            scattered.extend(squad_members);
+            commands.entity(squad_entity).despawn();
+            formation_maker.forget(squad_entity);
+            continue;
+        }
+
+        if squad.members != count {
+            squad.members = count;
+        }
+        let mut slowest = f32::MAX;
+        for (slot, (.., entity)) in squad_members.into_iter().enumerate() {
+            if let Ok((_, mut member, ..)) = member_query.get_mut(entity) {
+                if member.slot != slot {
+                    member.slot = slot;
+                }
+                slowest = slowest.min(member.speed);
+            }
+        }
+        formation.speed = slowest;
+    }
+
+    // Along with whatever was in a squad that's already gone, in an order the simulation
+    // owns since each of them takes from the `GameRng`.
+    scattered.extend(members.into_values().flatten());
+    scattered.sort_unstable_by_key(|&(_, spawn_tick, _)| spawn_tick);
+    for (.., entity) in scattered {
+        if let Ok((_, member, _, transform)) = member_query.get(entity) {
+            scatter(
+                &mut commands,
+                &config,
+                &win_size,
+                &mut rng,
+                entity,
+                member,
+                transform,
+            );
+        }
+    }
+}
+
+/// Send a squad member off on a pattern of its own.
+fn scatter(
+    commands: &mut Commands,
+    config: &GameConfig,
+    win_size: &WinSize,
+    rng: &mut GameRng,
+    entity: Entity,
+    member: &SquadMember,
+    transform: &Transform,
+) {
+    let side = if transform.translation.x < 0.0 {
+        Side::Left
+    } else {
+        Side::Right
+    };
+    let kind = *PatternKind::ALL.choose(rng).unwrap();
+    let mut formation = Formation::new(config, win_size, kind, side, rng);
+    formation.speed = member.speed;
+    commands
+        .entity(entity)
+        .remove::<SquadMember>()
+        .insert(formation);
+}
+
+/// Move squad members towards their places around the squad, catching up if they've fallen
+/// behind.
+pub fn squad_member_system(
+    squad_query: Query<(&Transform, &Squad), Without<SquadMember>>,
+    mut query: Query<(&mut Transform, &SquadMember), Without<Squad>>,
+) {
+    for (mut transform, member) in query.iter_mut() {
+        let (squad_tf, squad) = match squad_query.get(member.squad) {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+
+        let place =
+            squad_tf.translation.truncate() + squad.shape.offset(member.slot, squad.members);
+        let from = transform.translation.truncate();
+        let max_distance = member.speed * SQUAD_CATCH_UP * TIME_STEP;
+        let to = if from.distance(place) <= max_distance {
+            place
+        } else {
+            from + (place - from).normalize() * max_distance
+        };
+
+        if to.x != from.x {
+            let facing = if to.x < from.x { -1.0 } else { 1.0 };
+            transform.scale.x = transform.scale.x.abs() * facing;
+        }
+        let translation = &mut transform.translation;
+        (translation.x, translation.y) = (to.x, to.y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn members_keep_their_distance() {
+        for shape in SquadShape::ALL {
+            for count in 1..=8 {
+                let offsets: Vec<_> = (0..count).map(|slot| shape.offset(slot, count)).collect();
+                for (i, a) in offsets.iter().enumerate() {
+                    for b in &offsets[i + 1..] {
+                        assert!(
+                            a.distance(*b) >= SQUAD_SPACING - 0.01,
+                            "{:?} of {}: {} and {}",
+                            shape,
+                            count,
+                            a,
+                            b
+                        );
+                    }
+                }
+            }
+        }
+        assert_eq!(SquadShape::V.offset(0, 5), Vec2::ZERO);
+    }
+}
@@ -0,0 +1,253 @@
+use bevy::prelude::Component;
+use rand::{seq::SliceRandom, Rng};
+
+use super::PatternKind;
+use crate::{collision::Hitbox, ENEMY_HITBOX};
+
+/// How an enemy type gets around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Movement {
+    /// Follow a pattern along with the rest of its formation.
+    Formation(PatternKind),
+    /// Walk back and forth across the screen.
+    Walk,
+}
+
+impl Movement {
+    /// The movement a wave script calls a formation, in snake case: `walk` or the name of a
+    /// pattern.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "walk" => Some(Movement::Walk),
+            _ => PatternKind::from_name(name).map(Movement::Formation),
+        }
+    }
+}
+
+/// How an enemy type fires, and as a component how an enemy fires right now.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FirePattern {
+    /// One shot straight down.
+    Single,
+    /// One shot at the player.
+    Aimed,
+    /// That many shots fanned out downwards.
+    Spread(u32),
+}
+
+#[derive(Clone, Debug)]
+pub struct EnemyType {
+    pub name: &'static str,
+    /// Sheet in the sprite manifest, and the clip it plays.
+    pub sheet: &'static str,
+    pub clip: &'static str,
+    /// Scale on top of the `sprite_scale`.
+    pub scale: f32,
+    /// The whole sprite if not given.
+    pub hitbox: Option<Hitbox>,
+    pub hit_points: u32,
+    /// Speed relative to the `base_speed`.
+    pub speed: f32,
+    pub movement: Movement,
+    pub fire: FirePattern,
+    pub score: u32,
+    /// First wave the type turns up in.
+    pub first_wave: u32,
+    /// Chance of being picked, relative to the other types in the wave.
+    pub weight: u32,
+    /// What a boss switches to as it loses health, in order. Only bosses have any, the rest of
+    /// its type being its first phase.
+    pub phases: Vec<BossPhase>,
+}
+
+impl EnemyType {
+    /// Bosses turn up on their own in boss waves, never among other enemies.
+    pub fn is_boss(&self) -> bool {
+        !self.phases.is_empty()
+    }
+}
+
+/// A change of tactics for a boss.
+#[derive(Clone, Debug)]
+pub struct BossPhase {
+    /// Starts once health drops to this fraction of the hit points.
+    pub health: f32,
+    pub clip: &'static str,
+    pub speed: f32,
+    pub movement: Movement,
+    pub fire: FirePattern,
+    /// Its [`FireRate`](crate::components::FireRate) in this phase.
+    pub fire_rate: f64,
+}
+
+/// Every type of enemy, by name.
+pub struct EnemyTypes(Vec<EnemyType>);
+
+impl Default for EnemyTypes {
+    fn default() -> Self {
+        Self(vec![
+            EnemyType {
+                name: "ninja_cat",
+                sheet: "ninja_cat",
+                clip: "fly",
+                scale: 1.0,
+                hitbox: Some(Hitbox::rect(ENEMY_HITBOX.0, ENEMY_HITBOX.1)),
+                hit_points: 1,
+                speed: 1.0,
+                movement: Movement::Formation(PatternKind::Orbit),
+                fire: FirePattern::Single,
+                score: 1,
+                first_wave: 1,
+                weight: 3,
+                phases: Vec::new(),
+            },
+            EnemyType {
+                name: "penguin",
+                sheet: "penguin",
+                clip: "walk",
+                scale: 1.5,
+                hitbox: None,
+                hit_points: 2,
+                speed: 0.4,
+                movement: Movement::Walk,
+                fire: FirePattern::Aimed,
+                score: 2,
+                first_wave: 2,
+                weight: 2,
+                phases: Vec::new(),
+            },
+            EnemyType {
+                name: "star",
+                sheet: "star",
+                clip: "idle",
+                scale: 1.5,
+                hitbox: None,
+                hit_points: 3,
+                speed: 1.4,
+                movement: Movement::Formation(PatternKind::Orbit),
+                fire: FirePattern::Spread(3),
+                score: 3,
+                first_wave: 3,
+                weight: 1,
+                phases: Vec::new(),
+            },
+            EnemyType {
+                name: "ninja_cat_boss",
+                sheet: "ninja_cat_boss",
+                clip: "attack_sword",
+                scale: 1.5,
+                hitbox: None,
+                hit_points: 40,
+                speed: 0.3,
+                movement: Movement::Walk,
+                fire: FirePattern::Aimed,
+                score: 20,
+                first_wave: 1,
+                weight: 1,
+                phases: vec![
+                    BossPhase {
+                        health: 0.6,
+                        clip: "attack_kick",
+                        speed: 0.8,
+                        movement: Movement::Formation(PatternKind::Orbit),
+                        fire: FirePattern::Spread(5),
+                        fire_rate: 1.0,
+                    },
+                    BossPhase {
+                        health: 0.3,
+                        clip: "attack_punch",
+                        speed: 0.6,
+                        movement: Movement::Walk,
+                        fire: FirePattern::Spread(3),
+                        fire_rate: 2.5,
+                    },
+                ],
+            },
+        ])
+    }
+}
+
+impl EnemyTypes {
+    pub fn get(&self, name: &str) -> Option<&EnemyType> {
+        self.0.iter().find(|enemy_type| enemy_type.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &EnemyType> {
+        self.0.iter()
+    }
+
+    /// Pick one of the types that turn up by `wave`, or the first type if none do.
+    pub fn choose(&self, wave: u32, rng: &mut impl Rng) -> &EnemyType {
+        self.choose_where(wave, false, rng).unwrap_or(&self.0[0])
+    }
+
+    /// Pick one of the bosses that turn up by `wave`, if any do.
+    pub fn choose_boss(&self, wave: u32, rng: &mut impl Rng) -> Option<&EnemyType> {
+        self.choose_where(wave, true, rng)
+    }
+
+    fn choose_where(&self, wave: u32, boss: bool, rng: &mut impl Rng) -> Option<&EnemyType> {
+        let available: Vec<_> = self
+            .0
+            .iter()
+            .filter(|enemy_type| enemy_type.first_wave <= wave && enemy_type.is_boss() == boss)
+            .collect();
+        available
+            .choose_weighted(rng, |enemy_type| enemy_type.weight)
+            .ok()
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Handle;
+
+    use super::*;
+    use crate::{
+        asset_file,
+        rng::GameRng,
+        sprites::{SpriteManifest, SPRITES_ASSET},
+        GameTextures,
+    };
+
+    #[test]
+    fn every_type_has_its_sprites() {
+        let manifest = SpriteManifest::load(&asset_file(SPRITES_ASSET)).unwrap();
+        let textures = GameTextures::new(&manifest, |_| Handle::default()).unwrap();
+        for enemy_type in EnemyTypes::default().iter() {
+            let sheet = &textures.enemies[enemy_type.sheet];
+            let clips = enemy_type.phases.iter().map(|phase| phase.clip);
+            for clip in std::iter::once(enemy_type.clip).chain(clips) {
+                assert!(
+                    sheet.clips.contains_key(clip),
+                    "{}: {}",
+                    enemy_type.name,
+                    clip
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn later_waves_have_more_types() {
+        let types = EnemyTypes::default();
+        let mut rng = GameRng::new(1);
+        let mut chosen = |wave| {
+            let mut names: Vec<_> = (0..100)
+                .map(|_| types.choose(wave, &mut rng).name)
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            names
+        };
+
+        assert_eq!(chosen(1), ["ninja_cat"]);
+        assert_eq!(chosen(2), ["ninja_cat", "penguin"]);
+        assert_eq!(chosen(5), ["ninja_cat", "penguin", "star"]);
+        assert_eq!(
+            types.choose_boss(5, &mut rng).map(|boss| boss.name),
+            Some("ninja_cat_boss")
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use std::{fmt, io};
+
+/// Why a RON file the game reads couldn't be used.
+#[derive(Debug)]
+pub enum AssetError {
+    Io(io::Error),
+    Ron(ron::Error),
+    /// Every problem found with what it says.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(err) => write!(f, "{}", err),
+            AssetError::Ron(err) => write!(f, "{}", err),
+            AssetError::Invalid(problems) => write!(f, "{}", problems.join("; ")),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+impl From<io::Error> for AssetError {
+    fn from(err: io::Error) -> Self {
+        AssetError::Io(err)
+    }
+}
+
+impl From<ron::Error> for AssetError {
+    fn from(err: ron::Error) -> Self {
+        AssetError::Ron(err)
+    }
+}
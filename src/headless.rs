@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+
+use crate::{
+    simulation::{ManualSimulation, SimulationClock},
+    wave::WaveScript,
+    GameState, GameTextures, WinSize, TIME_STEP,
+};
+
+pub const HEADLESS_WIN_SIZE: WinSize = WinSize {
+    width: 1280.0,
+    height: 720.0,
+};
+
+/// Runs the game without a window, on top of `MinimalPlugins`.
+///
+/// Uses a fixed `WinSize`, textures without images and no scripted waves, and the simulation
+/// only advances through [`run_ticks`]. Insert a `WaveScript` after adding it to play one.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(bevy::input::InputPlugin)
+            .insert_resource(HEADLESS_WIN_SIZE)
+            .insert_resource(
+                GameTextures::placeholder()
+                    .unwrap_or_else(|err| panic!("Invalid sprite manifest: {}", err)),
+            )
+            .insert_resource(WaveScript::default())
+            .insert_resource(ManualSimulation);
+    }
+}
+
+/// Leave the title screen and start a new game.
+pub fn start_game(app: &mut App) {
+    app.update();
+    app.world
+        .resource_mut::<State<GameState>>()
+        .set(GameState::Playing)
+        .unwrap();
+    app.update();
+}
+
+/// Run `ticks` simulation ticks, one update per tick while the game is being played.
+pub fn run_ticks(app: &mut App, ticks: u64) {
+    for _ in 0..ticks {
+        if *app.world.resource::<State<GameState>>().current() == GameState::Playing {
+            app.world
+                .resource_mut::<SimulationClock>()
+                .advance(TIME_STEP as f64);
+        }
+        app.update();
+    }
+}
@@ -0,0 +1,370 @@
+use bevy::{app::AppExit, ecs::system::EntityCommands, prelude::*};
+
+use crate::{
+    components::{
+        BossHealthBar, BossHealthFill, Health, LivesDisplay, ScoreText, ScreenText, WaveBanner,
+        WaveText,
+    },
+    enemy::Boss,
+    sprites::LoadingFailed,
+    wave::{WavePhase, WaveState},
+    GameState, PlayerState, Scoreboard, BOSS_BAR_COLOR, LIFE_ICON, LIFE_ICON_SIZE,
+};
+
+/// Score, lives, wave, boss health and the title, pause and game over screens.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(hud_setup_system)
+            .add_system(game_state_keyboard_system)
+            .add_system(screen_text_system)
+            .add_system(loading_failed_system)
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(game_over_system))
+            .add_system(scoreboard_system)
+            .add_system(lives_display_system)
+            .add_system(wave_display_system)
+            .add_system(boss_health_system);
+    }
+}
+
+struct LifeIcon(Handle<Image>);
+
+fn hud_setup_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(LifeIcon(asset_server.load(LIFE_ICON)));
+
+    commands.spawn_bundle(UiCameraBundle::default());
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text {
+                sections: vec![
+                    TextSection {
+                        value: "Score: ".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 40.0,
+                            color: Color::rgb(0.5, 0.5, 1.0),
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+                            font_size: 40.0,
+                            color: Color::rgb(1.0, 0.5, 0.5),
+                        },
+                    },
+                ],
+                ..Default::default()
+            },
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    left: Val::Px(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ScoreText);
+
+    commands
+        .spawn_bundle(TextBundle {
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 30.0,
+                    color: Color::rgb(0.5, 0.5, 1.0),
+                },
+                Default::default(),
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(50.0),
+                    left: Val::Px(5.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(WaveText);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(5.0),
+                    right: Val::Px(5.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::RowReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(LivesDisplay);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(10.0),
+                    left: Val::Percent(30.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Percent(40.0), Val::Px(20.0)),
+                padding: Rect::all(Val::Px(3.0)),
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..Default::default()
+        })
+        .insert(BossHealthBar)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..Default::default()
+                    },
+                    color: BOSS_BAR_COLOR.into(),
+                    ..Default::default()
+                })
+                .insert(BossHealthFill);
+        });
+
+    spawn_screen_text(&mut commands, &asset_server, GameState::Loading, "Loading");
+    spawn_screen_text(
+        &mut commands,
+        &asset_server,
+        GameState::Title,
+        "Monkey Fire\nPress Enter to start",
+    );
+    spawn_screen_text(
+        &mut commands,
+        &asset_server,
+        GameState::Paused,
+        "Paused\nPress Escape to resume",
+    );
+    spawn_screen_text(&mut commands, &asset_server, GameState::GameOver, "");
+    spawn_centered_text(&mut commands, &asset_server, "").insert(WaveBanner);
+}
+
+fn spawn_screen_text(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    state: GameState,
+    value: &str,
+) {
+    spawn_centered_text(commands, asset_server, value).insert(ScreenText(state));
+}
+
+/// Hidden text in the middle of the screen, returning the text entity.
+fn spawn_centered_text<'w, 's, 'a>(
+    commands: &'a mut Commands<'w, 's>,
+    asset_server: &AssetServer,
+    value: &str,
+) -> EntityCommands<'w, 's, 'a> {
+    let mut text = None;
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            let entity = parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        value,
+                        TextStyle {
+                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                            font_size: 60.0,
+                            color: Color::rgb(1.0, 1.0, 0.5),
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .id();
+            text = Some(entity);
+        });
+    commands.entity(text.unwrap())
+}
+
+fn game_state_keyboard_system(
+    mut kb: ResMut<Input<KeyCode>>,
+    mut state: ResMut<State<GameState>>,
+    loading_failed: Option<Res<LoadingFailed>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let result = match state.current() {
+        GameState::Loading if loading_failed.is_some() && kb.just_pressed(KeyCode::Escape) => {
+            exit.send(AppExit);
+            Ok(())
+        }
+        GameState::Title | GameState::GameOver if kb.just_pressed(KeyCode::Return) => {
+            kb.reset(KeyCode::Return);
+            state.set(GameState::Playing)
+        }
+        GameState::Playing if kb.just_pressed(KeyCode::Escape) => {
+            kb.reset(KeyCode::Escape);
+            state.push(GameState::Paused)
+        }
+        GameState::Paused if kb.just_pressed(KeyCode::Escape) => {
+            kb.reset(KeyCode::Escape);
+            state.pop()
+        }
+        _ => Ok(()),
+    };
+
+    if let Err(err) = result {
+        warn!("Failed to change game state: {:?}", err);
+    }
+}
+
+fn screen_text_system(
+    state: Res<State<GameState>>,
+    mut query: Query<(&ScreenText, &mut Visibility)>,
+) {
+    if state.is_changed() {
+        for (screen_text, mut visibility) in query.iter_mut() {
+            visibility.is_visible = screen_text.0 == *state.current();
+        }
+    }
+}
+
+/// Say why on the loading screen, rather than sit there loading forever.
+fn loading_failed_system(
+    loading_failed: Option<Res<LoadingFailed>>,
+    mut query: Query<(&ScreenText, &mut Text)>,
+) {
+    let loading_failed = match loading_failed {
+        Some(loading_failed) if loading_failed.is_added() => loading_failed,
+        _ => return,
+    };
+    for (screen_text, mut text) in query.iter_mut() {
+        if screen_text.0 == GameState::Loading {
+            text.sections[0].value = format!("{}\nPress Escape to quit", loading_failed.0);
+        }
+    }
+}
+
+fn game_over_system(scoreboard: Res<Scoreboard>, mut query: Query<(&ScreenText, &mut Text)>) {
+    for (screen_text, mut text) in query.iter_mut() {
+        if screen_text.0 == GameState::GameOver {
+            text.sections[0].value = format!(
+                "Game Over\nFinal score: {}\nPress Enter to play again",
+                scoreboard.score
+            );
+        }
+    }
+}
+
+fn scoreboard_system(scoreboard: Res<Scoreboard>, mut query: Query<&mut Text, With<ScoreText>>) {
+    for mut text in query.iter_mut() {
+        text.sections[1].value = scoreboard.score.to_string();
+    }
+}
+
+fn lives_display_system(
+    mut commands: Commands,
+    player_state: Res<PlayerState>,
+    life_icon: Res<LifeIcon>,
+    query: Query<Entity, With<LivesDisplay>>,
+) {
+    if !player_state.is_changed() {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|parent| {
+            for _ in 0..player_state.lives {
+                parent.spawn_bundle(ImageBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(LIFE_ICON_SIZE), Val::Px(LIFE_ICON_SIZE)),
+                        ..Default::default()
+                    },
+                    image: life_icon.0.clone().into(),
+                    ..Default::default()
+                });
+            }
+        });
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn wave_display_system(
+    wave: Res<WaveState>,
+    state: Res<State<GameState>>,
+    mut text_query: Query<&mut Text, With<WaveText>>,
+    mut banner_query: Query<(&mut Text, &mut Visibility), (With<WaveBanner>, Without<WaveText>)>,
+) {
+    if !wave.is_changed() && !state.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = match wave.number {
+            0 => String::new(),
+            number => format!("Wave {}", number),
+        };
+    }
+
+    let breather = *state.current() == GameState::Playing && wave.phase == WavePhase::Breather;
+    for (mut text, mut visibility) in banner_query.iter_mut() {
+        text.sections[0].value = format!("Wave {}", wave.number);
+        visibility.is_visible = breather;
+    }
+}
+
+fn boss_health_system(
+    boss_query: Query<(&Boss, &Health)>,
+    mut bar_query: Query<&mut Style, (With<BossHealthBar>, Without<BossHealthFill>)>,
+    mut fill_query: Query<&mut Style, With<BossHealthFill>>,
+) {
+    let health_left = boss_query
+        .iter()
+        .next()
+        .map(|(boss, health)| boss.health_left(health));
+
+    // Only touch the styles when they change, so the layout isn't redone every frame.
+    let display = match health_left {
+        Some(_) => Display::Flex,
+        None => Display::None,
+    };
+    for mut style in bar_query.iter_mut() {
+        if style.display != display {
+            style.display = display;
+        }
+    }
+
+    if let Some(health_left) = health_left {
+        let width = Val::Percent(100.0 * health_left);
+        for mut style in fill_query.iter_mut() {
+            if style.size.width != width {
+                style.size.width = width;
+            }
+        }
+    }
+}
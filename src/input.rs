@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::{
+    replay::{ReplayPlayback, ReplayRecording},
+    simulation::{SimulationClock, SimulationStage},
+    GameState,
+};
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerInput::default())
+            .insert_resource(KeyboardLatch::default())
+            .add_system(keyboard_latch_system)
+            .add_system_to_stage(SimulationStage, player_input_system.label(PlayerInputLabel))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(input_reset_system),
+            );
+    }
+}
+
+/// Label for the system filling in [`PlayerInput`] at the start of every tick.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct PlayerInputLabel;
+
+/// What the player is doing during the current simulation tick.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlayerInput {
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+}
+
+/// Remembers key presses between ticks, so frames without a tick don't lose them.
+#[derive(Default)]
+struct KeyboardLatch {
+    fire: bool,
+}
+
+fn keyboard_latch_system(kb: Res<Input<KeyCode>>, mut latch: ResMut<KeyboardLatch>) {
+    if kb.just_pressed(KeyCode::Space) {
+        latch.fire = true;
+    }
+}
+
+fn player_input_system(
+    kb: Res<Input<KeyCode>>,
+    clock: Res<SimulationClock>,
+    mut latch: ResMut<KeyboardLatch>,
+    mut input: ResMut<PlayerInput>,
+    playback: Option<Res<ReplayPlayback>>,
+    recording: Option<ResMut<ReplayRecording>>,
+) {
+    *input = match playback {
+        Some(playback) => playback.input(clock.tick),
+        None => PlayerInput {
+            left: kb.pressed(KeyCode::Left),
+            right: kb.pressed(KeyCode::Right),
+            fire: latch.fire,
+        },
+    };
+    latch.fire = false;
+
+    if let Some(mut recording) = recording {
+        recording.push(*input);
+    }
+}
+
+fn input_reset_system(mut input: ResMut<PlayerInput>, mut latch: ResMut<KeyboardLatch>) {
+    *input = PlayerInput::default();
+    *latch = KeyboardLatch::default();
+}
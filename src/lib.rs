@@ -0,0 +1,279 @@
+use std::path::PathBuf;
+
+use bevy::{asset::FileAssetIo, prelude::*};
+use components::{Dying, Enemy, Explosion, Fire, Movable, OnOutsideWindow, Player, Velocity};
+
+use crate::{config::GameConfig, enemy::Squad, rng::GameRng, simulation::SimulationStage};
+
+pub mod animation;
+pub mod collision;
+pub mod combat;
+pub mod components;
+pub mod config;
+pub mod debug;
+pub mod enemy;
+pub mod error;
+pub mod headless;
+pub mod hud;
+pub mod input;
+pub mod player;
+pub mod replay;
+pub mod rng;
+pub mod simulation;
+pub mod sprites;
+pub mod wave;
+
+pub use sprites::GameTextures;
+
+pub const PLAYER_HITBOX: &[(f32, f32)] = &[
+    (-45.0, -76.0),
+    (40.0, -76.0),
+    (45.0, 30.0),
+    (15.0, 76.0),
+    (-30.0, 70.0),
+    (-50.0, 30.0),
+];
+
+pub const PLAYER_FIRE_RADIUS: f32 = 30.0;
+
+// The cat only fills the left half of each frame.
+pub const ENEMY_HITBOX: ((f32, f32), (f32, f32)) = ((-59.0, -5.0), (110.0, 190.0));
+
+pub const ENEMY_FIRE_INSET: (f32, f32) = (6.0, 10.0);
+
+pub const LIFE_ICON: &str =
+    "Char_Monkey_Free_Images/Icons and Large Images/monkey_head_400x400.png";
+pub const LIFE_ICON_SIZE: f32 = 40.0;
+
+pub const HURT_DURATION: f64 = 0.15;
+pub const HURT_COLOR: Color = Color::rgb(1.0, 0.4, 0.4);
+/// Seconds the player's body lies there before exploding.
+pub const DYING_DURATION: f64 = 0.6;
+
+/// Explosions going off around a boss when it is destroyed.
+pub const BOSS_EXPLOSIONS: usize = 6;
+pub const BOSS_BAR_COLOR: Color = Color::rgb(0.9, 0.2, 0.2);
+
+/// Room between neighbours in a squad.
+pub const SQUAD_SPACING: f32 = 70.0;
+pub const SQUAD_GRID_COLUMNS: usize = 3;
+/// How much faster than their own speed squad members go to get back in place.
+pub const SQUAD_CATCH_UP: f32 = 1.5;
+/// A squad scatters once less than this fraction of its members are left.
+pub const SQUAD_SCATTER: f32 = 0.5;
+
+pub const TIME_STEP: f32 = 1.0 / 60.0;
+
+// Defaults for `GameConfig`.
+pub const SPRITE_SCALE: f32 = 0.5;
+pub const BASE_SPEED: f32 = 500.0;
+pub const PLAYER_RESPAWN_DELAY: f64 = 2.0;
+pub const PLAYER_INVULNERABLE_DURATION: f32 = 2.0;
+pub const PLAYER_LIVES: u32 = 3;
+pub const PLAYER_EXTRA_LIFE_SCORE: u32 = 10;
+pub const ENEMY_MAX: u32 = 2;
+pub const FORMATION_MEMBERS_MAX: u32 = 2;
+pub const ENEMY_FIRE_RATE: f64 = 0.5;
+pub const WAVE_ENEMIES: u32 = 6;
+pub const WAVE_GROWTH: u32 = 2;
+pub const WAVE_BREATHER: f64 = 2.0;
+pub const WAVE_ESCALATION: f32 = 0.1;
+pub const BOSS_EVERY: u32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameState {
+    Loading,
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+pub struct WinSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Where the asset server finds `path`.
+pub fn asset_file(path: &str) -> PathBuf {
+    FileAssetIo::get_root_path().join("assets").join(path)
+}
+
+pub struct EnemyCount(pub u32);
+
+#[derive(Debug)]
+pub struct PlayerState {
+    pub on: bool,
+    pub last_shot: f64, // -1 if not shot
+    pub lives: u32,
+    pub next_extra_life: u32,
+}
+
+impl FromWorld for PlayerState {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(world.resource::<GameConfig>())
+    }
+}
+
+impl PlayerState {
+    pub fn new(config: &GameConfig) -> Self {
+        Self {
+            on: false,
+            last_shot: -1.0,
+            lives: config.player_lives,
+            next_extra_life: config.player_extra_life_score,
+        }
+    }
+
+    pub fn shot(&mut self, time: f64) {
+        self.on = false;
+        self.last_shot = time;
+        self.lives = self.lives.saturating_sub(1);
+    }
+
+    pub fn score_changed(&mut self, score: u32, extra_life_score: u32) {
+        while score >= self.next_extra_life {
+            self.lives += 1;
+            self.next_extra_life += extra_life_score;
+        }
+    }
+
+    pub fn spawned(&mut self) {
+        self.on = true;
+        self.last_shot = -1.0;
+    }
+}
+
+#[derive(Default)]
+pub struct Scoreboard {
+    pub score: u32,
+}
+
+/// The whole game in a window, with its HUD and debug overlay.
+///
+/// Expects `DefaultPlugins` and a `GameRng` resource to be provided.
+pub struct MonkeyFirePlugin;
+
+impl Plugin for MonkeyFirePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(GamePlugin)
+            .add_plugin(config::ConfigPlugin)
+            .add_plugin(sprites::SheetsPlugin)
+            .add_plugin(wave::WaveScriptPlugin)
+            .add_plugin(hud::HudPlugin)
+            .add_plugin(debug::DebugPlugin)
+            .add_startup_system(setup_system);
+    }
+}
+
+/// The rules of the game, without any window, rendering or HUD so it can also run headless.
+///
+/// Expects the `WinSize` and `GameRng` resources to be provided, and uses the default
+/// `GameConfig` and placeholder `GameTextures` unless they are. Starts out `Loading`, it is up
+/// to whoever provides the textures to move on from there.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameConfig>()
+            .init_resource::<GameTextures>()
+            .insert_resource(Scoreboard::default())
+            .insert_resource(EnemyCount(0))
+            .add_state(GameState::Loading)
+            .add_plugin(simulation::SimulationPlugin)
+            .add_plugin(animation::AnimationPlugin)
+            .add_plugin(input::InputPlugin)
+            .add_plugin(replay::ReplayPlugin)
+            .add_plugin(combat::CombatPlugin)
+            .add_plugin(player::PlayerPlugin)
+            .add_plugin(enemy::EnemyPlugin)
+            .add_plugin(wave::WavePlugin)
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(new_game_system))
+            .add_system_to_stage(SimulationStage, movable_system);
+    }
+}
+
+fn setup_system(mut commands: Commands, rng: Res<GameRng>, windows: Res<Windows>) {
+    info!("Using seed {}", rng.seed());
+
+    commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+
+    let window = windows.get_primary().unwrap();
+    let (win_w, win_h) = (window.width(), window.height());
+    let win_size = WinSize {
+        width: win_w,
+        height: win_h,
+    };
+    commands.insert_resource(win_size);
+}
+
+#[allow(clippy::type_complexity)]
+fn new_game_system(
+    mut commands: Commands,
+    mut scoreboard: ResMut<Scoreboard>,
+    mut enemy_count: ResMut<EnemyCount>,
+    mut rng: ResMut<GameRng>,
+    query: Query<
+        Entity,
+        Or<(
+            With<Player>,
+            With<Enemy>,
+            With<Fire>,
+            With<Explosion>,
+            With<Dying>,
+            With<Squad>,
+        )>,
+    >,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    *scoreboard = Scoreboard::default();
+    *enemy_count = EnemyCount(0);
+    rng.new_game();
+    info!("Game seed {}", rng.game_seed());
+}
+
+pub fn movable_system(
+    mut commands: Commands,
+    win_size: Res<WinSize>,
+    config: Res<GameConfig>,
+    mut query: Query<(Entity, &Velocity, &mut Transform, &Movable)>,
+) {
+    for (entity, velocity, mut transform, movable) in query.iter_mut() {
+        transform.translation.x += velocity.x * TIME_STEP * config.base_speed;
+        transform.translation.y += velocity.y * TIME_STEP * config.base_speed;
+
+        const MARGIN: f32 = 50.0;
+        let left = -win_size.width / 2.0 - MARGIN;
+        let right = win_size.width / 2.0 + MARGIN;
+        let top = win_size.height / 2.0 + MARGIN;
+        let bottom = -win_size.height / 2.0 - MARGIN;
+
+        let left_of_screen = transform.translation.x < left;
+        let right_of_screen = transform.translation.x > right;
+        let top_of_screen = transform.translation.y < -win_size.height / 2.0 - MARGIN;
+        let bottom_of_screen = transform.translation.y > win_size.height / 2.0 + MARGIN;
+
+        match movable.on_outside_window {
+            OnOutsideWindow::Despawn => {
+                if left_of_screen | right_of_screen | top_of_screen | bottom_of_screen {
+                    commands.entity(entity).despawn();
+                }
+            }
+            OnOutsideWindow::Wrap => {
+                if left_of_screen {
+                    transform.translation.x = right;
+                } else if right_of_screen {
+                    transform.translation.x = left;
+                }
+                if top_of_screen {
+                    transform.translation.y = bottom;
+                } else if bottom_of_screen {
+                    transform.translation.y = top;
+                }
+            }
+        }
+    }
+}
@@ -1,38 +1,67 @@
-use bevy::{core::FixedTimestep, prelude::*};
+use std::time::Duration;
+
+use bevy::prelude::*;
 
 use crate::{
+    animation::Animator,
+    collision::{CollisionLayer, CollisionLayers, Hitbox},
+    combat::{DamageLabel, DestroyedEvent},
     components::{
-        Animate, Fire, FromPlayer, Movable, OnOutsideWindow, Player, SpriteSize, Velocity,
+        Damage, Dying, Fire, Invulnerable, Movable, OnOutsideWindow, Player, SpriteSize, Velocity,
     },
-    GameTextures, PlayerState, WinSize, PLAYER_FIRE_SIZE, PLAYER_RESPAWN_DELAY, PLAYER_SIZE,
-    SPRITE_SCALE,
+    config::GameConfig,
+    input::{PlayerInput, PlayerInputLabel},
+    simulation::{every, SimulationClock, SimulationStage},
+    GameState, GameTextures, PlayerState, Scoreboard, WinSize, PLAYER_FIRE_RADIUS, PLAYER_HITBOX,
+    TIME_STEP,
 };
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(PlayerState::default())
+        app.init_resource::<PlayerState>()
             .insert_resource(PlayerSprite::default())
-            .add_system_set(
+            .add_system_set_to_stage(
+                SimulationStage,
                 SystemSet::new()
-                    .with_run_criteria(FixedTimestep::step(0.5))
+                    .with_run_criteria(every(0.5))
                     .with_system(player_spawn_system),
             )
-            .add_system(player_keyboard_event_system)
-            .add_system(player_fire_system)
-            .add_system(
-                player_animate
-                    .after(player_spawn_system)
-                    .after(player_keyboard_event_system),
+            .add_system_set_to_stage(
+                SimulationStage,
+                SystemSet::new()
+                    .with_system(player_movement_system.after(PlayerInputLabel))
+                    .with_system(player_fire_system.after(PlayerInputLabel))
+                    .with_system(player_extra_life_system)
+                    .with_system(player_invulnerable_system)
+                    .with_system(player_celebrate_system.after(DamageLabel))
+                    .with_system(player_death_system.after(DamageLabel)),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(player_animate))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(player_reset_system),
             );
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PlayerAnimation {
     Idle,
-    Walking,
+    Running,
+    Throwing,
+    Celebrating,
+}
+
+impl PlayerAnimation {
+    fn clip(self) -> &'static str {
+        match self {
+            PlayerAnimation::Idle => "idle",
+            PlayerAnimation::Running => "run",
+            PlayerAnimation::Throwing => "throw",
+            PlayerAnimation::Celebrating => "cheer",
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -45,6 +74,8 @@ pub enum PlayerDirection {
 struct PlayerSprite {
     pub state: PlayerAnimation,
     pub direction: PlayerDirection,
+    /// One-off animation to play over the idle or running one.
+    pub action: Option<PlayerAnimation>,
 }
 
 impl Default for PlayerSprite {
@@ -52,61 +83,115 @@ impl Default for PlayerSprite {
         Self {
             state: PlayerAnimation::Idle,
             direction: PlayerDirection::Left,
+            action: None,
         }
     }
 }
 
+fn player_reset_system(
+    config: Res<GameConfig>,
+    mut player_state: ResMut<PlayerState>,
+    mut sprite: ResMut<PlayerSprite>,
+) {
+    *player_state = PlayerState::new(&config);
+    *sprite = PlayerSprite::default();
+}
+
+fn player_extra_life_system(
+    config: Res<GameConfig>,
+    scoreboard: Res<Scoreboard>,
+    mut player_state: ResMut<PlayerState>,
+) {
+    if scoreboard.is_changed() {
+        player_state.score_changed(scoreboard.score, config.player_extra_life_score);
+    }
+}
+
 fn player_spawn_system(
     mut commands: Commands,
     mut player_state: ResMut<PlayerState>,
-    time: Res<Time>,
+    mut state: ResMut<State<GameState>>,
+    clock: Res<SimulationClock>,
+    config: Res<GameConfig>,
     game_textures: Res<GameTextures>,
     win_size: Res<WinSize>,
 ) {
-    let now = time.seconds_since_startup();
+    let now = clock.seconds();
     let last_shot = player_state.last_shot;
 
-    if !player_state.on && (last_shot == -1.0 || now > last_shot + PLAYER_RESPAWN_DELAY) {
+    if player_state.on || (last_shot != -1.0 && now <= last_shot + config.player_respawn_delay) {
+        return;
+    }
+
+    if player_state.lives == 0 {
+        if let Err(err) = state.set(GameState::GameOver) {
+            warn!("Failed to end game: {:?}", err);
+        }
+    } else {
+        let sheet = &game_textures.player;
+        let animator = sheet.animator("idle");
         let bottom = -win_size.height / 2.0;
+        let scale = config.sprite_scale;
         commands
             .spawn_bundle(SpriteSheetBundle {
-                texture_atlas: game_textures.player.clone(),
+                texture_atlas: sheet.atlas.clone(),
                 sprite: TextureAtlasSprite {
-                    index: 6,
+                    index: animator.index(),
                     ..Default::default()
                 },
                 transform: Transform {
-                    translation: Vec3::new(0.0, bottom + PLAYER_SIZE.1 / 2.0 * SPRITE_SCALE, 10.0),
-                    scale: Vec3::new(SPRITE_SCALE, SPRITE_SCALE, 1.0),
+                    translation: Vec3::new(0.0, bottom + sheet.tile_size.y / 2.0 * scale, 10.0),
+                    scale: Vec3::new(scale, scale, 1.0),
                     ..Default::default()
                 },
                 ..Default::default()
             })
             .insert(Player)
-            .insert(SpriteSize::from(PLAYER_SIZE))
+            .insert(SpriteSize(sheet.tile_size))
+            .insert(Hitbox::polygon(PLAYER_HITBOX))
+            .insert(CollisionLayers::new(CollisionLayer::Player, &[]))
             .insert(Movable {
                 on_outside_window: OnOutsideWindow::Wrap,
             })
             .insert(Velocity { x: 0.0, y: 0.0 })
-            .insert(Animate {
-                range: 6..=6,
-                ..Default::default()
-            });
+            .insert(animator)
+            .insert(Invulnerable::new(config.player_invulnerable_duration));
 
         player_state.spawned();
     }
 }
 
-fn player_keyboard_event_system(
-    kb: Res<Input<KeyCode>>,
+fn player_invulnerable_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Invulnerable, &mut TextureAtlasSprite), With<Player>>,
+) {
+    let delta = Duration::from_secs_f32(TIME_STEP);
+
+    for (entity, mut invulnerable, mut sprite) in query.iter_mut() {
+        invulnerable.timer.tick(delta);
+        invulnerable.blink.tick(delta);
+
+        if invulnerable.timer.finished() {
+            sprite.color.set_a(1.0);
+            commands.entity(entity).remove::<Invulnerable>();
+        } else if invulnerable.blink.just_finished() {
+            let alpha = if sprite.color.a() < 1.0 { 1.0 } else { 0.2 };
+            sprite.color.set_a(alpha);
+        }
+    }
+}
+
+fn player_movement_system(
+    input: Res<PlayerInput>,
+    config: Res<GameConfig>,
     mut sprite: ResMut<PlayerSprite>,
     mut query: Query<(&mut Velocity, &mut Transform), With<Player>>,
 ) {
     if let Ok((mut velocity, mut transform)) = query.get_single_mut() {
-        let (direction, animation, velocity_x) = if kb.pressed(KeyCode::Left) {
-            (PlayerDirection::Left, PlayerAnimation::Walking, -1.0)
-        } else if kb.pressed(KeyCode::Right) {
-            (PlayerDirection::Right, PlayerAnimation::Walking, 1.0)
+        let (direction, animation, velocity_x) = if input.left {
+            (PlayerDirection::Left, PlayerAnimation::Running, -1.0)
+        } else if input.right {
+            (PlayerDirection::Right, PlayerAnimation::Running, 1.0)
         } else {
             (sprite.direction, PlayerAnimation::Idle, 0.0)
         };
@@ -116,53 +201,107 @@ fn player_keyboard_event_system(
         velocity.x = velocity_x;
 
         transform.scale.x = match sprite.direction {
-            PlayerDirection::Left => -1.0 * SPRITE_SCALE,
-            PlayerDirection::Right => 1.0 * SPRITE_SCALE,
+            PlayerDirection::Left => -config.sprite_scale,
+            PlayerDirection::Right => config.sprite_scale,
         }
     }
 }
 
 fn player_fire_system(
     mut commands: Commands,
-    kb: Res<Input<KeyCode>>,
+    input: Res<PlayerInput>,
+    config: Res<GameConfig>,
     game_textures: Res<GameTextures>,
+    mut sprite: ResMut<PlayerSprite>,
     query: Query<&Transform, With<Player>>,
 ) {
     if let Ok(player_tf) = query.get_single() {
-        if kb.just_pressed(KeyCode::Space) {
+        if input.fire {
+            sprite.action = Some(PlayerAnimation::Throwing);
+
             let (x, y) = (player_tf.translation.x, player_tf.translation.y);
+            let sheet = &game_textures.player_fire;
+            let scale = config.sprite_scale * 2.0;
 
             commands
                 .spawn_bundle(SpriteSheetBundle {
-                    texture_atlas: game_textures.player_fire.clone(),
+                    texture_atlas: sheet.atlas.clone(),
                     transform: Transform {
                         translation: Vec3::new(x, y, 0.0),
-                        scale: Vec3::new(SPRITE_SCALE * 2.0, SPRITE_SCALE * 2.0, 1.0),
+                        scale: Vec3::new(scale, scale, 1.0),
                         ..Default::default()
                     },
                     ..Default::default()
                 })
                 .insert(Fire)
-                .insert(FromPlayer)
-                .insert(SpriteSize::from(PLAYER_FIRE_SIZE))
+                .insert(Damage(1))
+                .insert(SpriteSize(sheet.tile_size))
+                .insert(Hitbox::Circle {
+                    radius: PLAYER_FIRE_RADIUS,
+                })
+                .insert(CollisionLayers::new(
+                    CollisionLayer::PlayerFire,
+                    &[CollisionLayer::Enemy, CollisionLayer::EnemyFire],
+                ))
                 .insert(Velocity { x: 0.0, y: 1.0 })
                 .insert(Movable {
                     on_outside_window: OnOutsideWindow::Despawn,
                 })
-                .insert(Animate {
-                    range: 0..=2,
+                .insert(sheet.animator("spin"));
+        }
+    }
+}
+
+fn player_celebrate_system(
+    mut events: EventReader<DestroyedEvent>,
+    mut sprite: ResMut<PlayerSprite>,
+) {
+    for event in events.iter() {
+        if event.target.layer == CollisionLayer::Enemy {
+            sprite.action = Some(PlayerAnimation::Celebrating);
+        }
+    }
+}
+
+/// Leave the player's body behind to play the dead pose before it explodes.
+fn player_death_system(
+    mut commands: Commands,
+    mut events: EventReader<DestroyedEvent>,
+    game_textures: Res<GameTextures>,
+    clock: Res<SimulationClock>,
+    query: Query<&Transform, With<Player>>,
+) {
+    for event in events.iter() {
+        if event.target.layer != CollisionLayer::Player {
+            continue;
+        }
+
+        if let Ok(&transform) = query.get(event.target.entity) {
+            let sheet = &game_textures.player;
+            let animator = sheet.animator("dead");
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas: sheet.atlas.clone(),
+                    sprite: TextureAtlasSprite {
+                        index: animator.index(),
+                        ..Default::default()
+                    },
+                    transform,
                     ..Default::default()
-                });
+                })
+                .insert(animator)
+                .insert(Dying::starting_at(clock.tick));
         }
     }
 }
 
-fn player_animate(sprite: Res<PlayerSprite>, mut query: Query<&mut Animate, With<Player>>) {
-    let range = match sprite.state {
-        PlayerAnimation::Idle => 6..=6,
-        PlayerAnimation::Walking => 0..=3,
-    };
-    if let Ok(mut animate) = query.get_single_mut() {
-        animate.range = range;
+fn player_animate(mut sprite: ResMut<PlayerSprite>, mut query: Query<&mut Animator, With<Player>>) {
+    let action = sprite.action.take();
+    if let Ok(mut animator) = query.get_single_mut() {
+        match action {
+            Some(action) => animator.restart(action.clip()),
+            None if !animator.playing_once() => animator.play(sprite.state.clip()),
+            None => {}
+        }
     }
 }
@@ -0,0 +1,167 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::AssetError, input::PlayerInput, rng::GameRng, GameState};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Playing).with_system(replay_reset_system),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(replay_save_system))
+        .add_system_to_stage(CoreStage::Last, replay_exit_system);
+    }
+}
+
+/// Everything needed to play a game again: the game's own seed and the input of every tick.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    /// Run length encoded, as `(ticks, input)` pairs.
+    inputs: Vec<(u64, PlayerInput)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, inputs: &[PlayerInput]) -> Self {
+        let mut encoded: Vec<(u64, PlayerInput)> = Vec::new();
+        for &input in inputs {
+            match encoded.last_mut() {
+                Some((ticks, last)) if *last == input => *ticks += 1,
+                _ => encoded.push((1, input)),
+            }
+        }
+
+        Self {
+            seed,
+            inputs: encoded,
+        }
+    }
+
+    pub fn inputs(&self) -> Vec<PlayerInput> {
+        self.inputs
+            .iter()
+            .flat_map(|&(ticks, input)| std::iter::repeat_n(input, ticks as usize))
+            .collect()
+    }
+
+    pub fn load(path: &PathBuf) -> Result<Self, AssetError> {
+        let contents = fs::read_to_string(path)?;
+        Ok(ron::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: &PathBuf) -> Result<(), AssetError> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+/// Feeds recorded inputs to the simulation in place of the keyboard.
+pub struct ReplayPlayback {
+    inputs: Vec<PlayerInput>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            inputs: replay.inputs(),
+        }
+    }
+
+    /// Input for the given tick, counting from 1. Nothing is pressed once the replay ends.
+    pub fn input(&self, tick: u64) -> PlayerInput {
+        let index = tick.saturating_sub(1) as usize;
+        if index == self.inputs.len() {
+            info!("Replay finished after {} ticks", self.inputs.len());
+        }
+        self.inputs.get(index).copied().unwrap_or_default()
+    }
+}
+
+/// Collects the input of every tick of the current game, to be saved to `path`.
+pub struct ReplayRecording {
+    path: PathBuf,
+    inputs: Vec<PlayerInput>,
+}
+
+impl ReplayRecording {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, input: PlayerInput) {
+        self.inputs.push(input);
+    }
+
+    fn save(&self, seed: u64) {
+        if self.inputs.is_empty() {
+            return;
+        }
+
+        match Replay::new(seed, &self.inputs).save(&self.path) {
+            Ok(()) => info!("Saved replay to {}", self.path.display()),
+            Err(err) => error!("Failed to save replay to {}: {}", self.path.display(), err),
+        }
+    }
+}
+
+fn replay_reset_system(recording: Option<ResMut<ReplayRecording>>) {
+    if let Some(mut recording) = recording {
+        recording.inputs.clear();
+    }
+}
+
+fn replay_save_system(recording: Option<Res<ReplayRecording>>, rng: Res<GameRng>) {
+    if let Some(recording) = recording {
+        recording.save(rng.game_seed());
+    }
+}
+
+fn replay_exit_system(
+    events: EventReader<AppExit>,
+    state: Res<State<GameState>>,
+    recording: Option<Res<ReplayRecording>>,
+    rng: Res<GameRng>,
+) {
+    // Games that already ended were saved on game over.
+    if events.is_empty() || *state.current() == GameState::GameOver {
+        return;
+    }
+
+    if let Some(recording) = recording {
+        recording.save(rng.game_seed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let fire = PlayerInput {
+            fire: true,
+            ..Default::default()
+        };
+        let left = PlayerInput {
+            left: true,
+            ..Default::default()
+        };
+        let inputs = vec![PlayerInput::default(), fire, left, left, left, fire];
+
+        let replay = Replay::new(42, &inputs);
+        assert_eq!(replay.inputs.len(), 4);
+
+        let text = ron::ser::to_string(&replay).unwrap();
+        let loaded: Replay = ron::from_str(&text).unwrap();
+        assert_eq!(loaded.seed, 42);
+        assert_eq!(loaded.inputs(), inputs);
+    }
+}
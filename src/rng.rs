@@ -0,0 +1,81 @@
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+
+/// Source of every random decision in the game, so that a seed reproduces a run.
+///
+/// Each game in a session gets its own seed, the session's seed for the first game and one
+/// more for each game after it, so they don't all play the same.
+pub struct GameRng {
+    seed: u64,
+    /// Games started so far.
+    games: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            games: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seed the session started with.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Seed of the game being played, which replays it on its own.
+    pub fn game_seed(&self) -> u64 {
+        self.seed.wrapping_add(self.games.saturating_sub(1))
+    }
+
+    /// Start the sequence for the next game.
+    pub fn new_game(&mut self) {
+        self.games += 1;
+        self.rng = StdRng::seed_from_u64(self.game_seed());
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_game_has_its_own_seed() {
+        let mut rng = GameRng::new(7);
+        rng.new_game();
+        assert_eq!(rng.game_seed(), 7);
+        let first = rng.next_u64();
+
+        rng.new_game();
+        assert_eq!(rng.game_seed(), 8);
+        assert_ne!(rng.next_u64(), first);
+
+        // A game played again from its own seed.
+        let mut replayed = GameRng::new(8);
+        replayed.new_game();
+        let mut second = GameRng::new(7);
+        second.new_game();
+        second.new_game();
+        assert_eq!(replayed.next_u64(), second.next_u64());
+    }
+}
@@ -0,0 +1,213 @@
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+
+use crate::{GameState, TIME_STEP};
+
+/// Never run more than this many seconds of catch up ticks in one frame.
+const MAX_CATCH_UP: f64 = 0.25;
+
+/// Stage holding the gameplay simulation, run once per `TIME_STEP` of played time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, StageLabel)]
+pub struct SimulationStage;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SimulationClock::default())
+            .add_stage_after(CoreStage::Update, SimulationStage, simulation_stage())
+            .add_system_to_stage(CoreStage::PreUpdate, simulation_clock_system)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing).with_system(simulation_reset_system),
+            );
+    }
+}
+
+/// When present the simulation clock ignores real time, and only advances when told to.
+pub struct ManualSimulation;
+
+/// Played time waiting to be simulated, and the number of ticks simulated so far.
+#[derive(Debug, Default)]
+pub struct SimulationClock {
+    accumulator: f64,
+    pub tick: u64,
+}
+
+impl SimulationClock {
+    pub fn advance(&mut self, seconds: f64) {
+        self.accumulator = (self.accumulator + seconds).min(MAX_CATCH_UP);
+    }
+
+    /// Simulated time since the game started.
+    pub fn seconds(&self) -> f64 {
+        self.tick as f64 * TIME_STEP as f64
+    }
+
+    fn next_tick(&mut self) -> ShouldRun {
+        if self.accumulator >= TIME_STEP as f64 {
+            self.accumulator -= TIME_STEP as f64;
+            self.tick += 1;
+            ShouldRun::YesAndCheckAgain
+        } else {
+            ShouldRun::No
+        }
+    }
+}
+
+/// Whole ticks in `seconds`.
+pub fn ticks(seconds: f64) -> u64 {
+    (seconds / TIME_STEP as f64).round() as u64
+}
+
+/// Single threaded, so systems always run in the same order and the simulation stays
+/// deterministic.
+pub fn simulation_stage() -> SystemStage {
+    SystemStage::single_threaded().with_run_criteria(simulation_criteria)
+}
+
+/// Run criteria passing on one simulation tick every `seconds`.
+pub fn every(seconds: f64) -> impl FnMut(Res<SimulationClock>) -> ShouldRun {
+    let ticks = ticks(seconds).max(1);
+    move |clock: Res<SimulationClock>| {
+        if clock.tick.is_multiple_of(ticks) {
+            ShouldRun::Yes
+        } else {
+            ShouldRun::No
+        }
+    }
+}
+
+fn simulation_criteria(mut clock: ResMut<SimulationClock>) -> ShouldRun {
+    clock.next_tick()
+}
+
+fn simulation_clock_system(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut clock: ResMut<SimulationClock>,
+    manual: Option<Res<ManualSimulation>>,
+) {
+    if manual.is_none() && *state.current() == GameState::Playing {
+        clock.advance(time.delta_seconds_f64());
+    }
+}
+
+fn simulation_reset_system(mut clock: ResMut<SimulationClock>) {
+    *clock = SimulationClock::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{Enemy, Movable, OnOutsideWindow, Velocity},
+        config::GameConfig,
+        enemy::{enemy_movement_system, Formation, Pattern},
+        movable_system, WinSize, BASE_SPEED,
+    };
+
+    const TICKS: u64 = 120;
+
+    /// Positions of every entity as they were at the end of tick `TICKS`.
+    #[derive(Default)]
+    struct Recorded(Vec<(Entity, Vec3)>);
+
+    fn record_system(
+        clock: Res<SimulationClock>,
+        mut recorded: ResMut<Recorded>,
+        query: Query<(Entity, &Transform)>,
+    ) {
+        if clock.tick == TICKS {
+            recorded.0 = query.iter().map(|(e, tf)| (e, tf.translation)).collect();
+        }
+    }
+
+    /// Run the movement systems at `fps` until `TICKS` ticks have been simulated.
+    fn run_at(fps: f64) -> (Vec3, Vec3) {
+        let mut world = World::new();
+        world.insert_resource(SimulationClock::default());
+        world.insert_resource(Recorded::default());
+        world.insert_resource(GameConfig::default());
+        world.insert_resource(WinSize {
+            width: 10000.0,
+            height: 10000.0,
+        });
+
+        let mover = world
+            .spawn()
+            .insert(Transform::default())
+            .insert(Velocity { x: 1.0, y: 0.5 })
+            .insert(Movable {
+                on_outside_window: OnOutsideWindow::Wrap,
+            })
+            .id();
+
+        let orbiter = world
+            .spawn()
+            .insert(Transform::from_xyz(-700.0, 100.0, 0.0))
+            .insert(Formation {
+                start: Vec2::new(-700.0, 100.0),
+                pattern: Pattern::Orbit {
+                    pivot: Vec2::new(0.0, 200.0),
+                    radius: Vec2::new(120.0, 100.0),
+                    clockwise: false,
+                },
+                t: 0.0,
+                speed: BASE_SPEED,
+            })
+            .insert(Enemy)
+            .id();
+
+        let mut stage = simulation_stage()
+            .with_system(movable_system)
+            .with_system(enemy_movement_system)
+            .with_system(
+                record_system
+                    .after(movable_system)
+                    .after(enemy_movement_system),
+            );
+
+        let mut elapsed = 0.0;
+        while world.resource::<SimulationClock>().tick < TICKS {
+            world.resource_mut::<SimulationClock>().advance(1.0 / fps);
+            elapsed += 1.0 / fps;
+            stage.run(&mut world);
+        }
+
+        let expected = TICKS as f64 * TIME_STEP as f64;
+        assert!(
+            (elapsed - expected).abs() <= 1.0 / fps + TIME_STEP as f64,
+            "{} fps took {}s to simulate {}s",
+            fps,
+            elapsed,
+            expected
+        );
+
+        let recorded = &world.resource::<Recorded>().0;
+        let position = |entity| recorded.iter().find(|(e, _)| *e == entity).unwrap().1;
+        (position(mover), position(orbiter))
+    }
+
+    #[test]
+    fn same_positions_at_any_frame_rate() {
+        let (mover, orbiter) = run_at(60.0);
+        assert!((mover.x - TICKS as f32 * TIME_STEP * BASE_SPEED).abs() < 0.01);
+
+        for fps in [20.0, 30.0, 75.0, 120.0, 144.0, 240.0] {
+            let (other_mover, other_orbiter) = run_at(fps);
+            assert!(
+                mover.distance(other_mover) < 0.01,
+                "{} fps: {} != {}",
+                fps,
+                other_mover,
+                mover
+            );
+            assert!(
+                orbiter.distance(other_orbiter) < 0.01,
+                "{} fps: {} != {}",
+                fps,
+                other_orbiter,
+                orbiter
+            );
+        }
+    }
+}
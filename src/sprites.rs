@@ -0,0 +1,408 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    ops::RangeInclusive,
+    path::Path,
+    sync::Arc,
+};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{Animator, Playback},
+    asset_file,
+    error::AssetError,
+    GameState,
+};
+
+/// Path of the sprite manifest in the assets folder.
+pub const SPRITES_ASSET: &str = "sprites.manifest.ron";
+
+/// Seconds each frame of a clip shows for, unless the manifest says otherwise.
+pub const FRAME_DURATION: f32 = 0.5;
+
+/// Sheets the game needs, with the clips it plays from each.
+const REQUIRED_CLIPS: &[(&str, &[&str])] = &[
+    ("player", &["idle", "run", "throw", "cheer", "dead"]),
+    ("player_fire", &["spin"]),
+    ("enemy_fire", &["flap"]),
+    ("explosion", &["explode"]),
+    // Sheets for the `EnemyTypes`.
+    ("ninja_cat", &["fly"]),
+    ("penguin", &["walk"]),
+    ("star", &["idle"]),
+    (
+        "ninja_cat_boss",
+        &["attack_sword", "attack_kick", "attack_punch"],
+    ),
+];
+
+/// Loads the sprite manifest and builds the `GameTextures` from it, then shows the title
+/// screen, or inserts a `LoadingFailed` if it can't. Needs the `AssetPlugin`.
+pub struct SheetsPlugin;
+
+impl Plugin for SheetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<SpriteManifest>()
+            .init_asset_loader::<SpriteManifestLoader>()
+            .add_startup_system(sheets_load_system)
+            .add_system_set(
+                SystemSet::on_update(GameState::Loading).with_system(sheets_ready_system),
+            );
+    }
+}
+
+/// Why the game can't get past loading.
+#[derive(Debug)]
+pub struct LoadingFailed(pub String);
+
+/// Every sprite sheet, split into a grid of equally sized tiles, and its animation clips.
+#[derive(Debug, Serialize, Deserialize, TypeUuid)]
+#[uuid = "5f7e1c3a-2b8d-4e6f-9c1a-3d4b5e6f7a8b"]
+#[serde(deny_unknown_fields)]
+pub struct SpriteManifest {
+    pub sheets: BTreeMap<String, SheetManifest>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SheetManifest {
+    /// Image path in the assets folder.
+    pub path: String,
+    /// Size of each tile, in unscaled pixels.
+    pub tile_size: (f32, f32),
+    pub columns: usize,
+    pub rows: usize,
+    #[serde(default)]
+    pub clips: BTreeMap<String, Clip>,
+}
+
+/// Frames `first` to `last` of a sheet, counting along the rows.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Clip {
+    pub first: usize,
+    pub last: usize,
+    /// Seconds each frame shows for.
+    #[serde(default = "default_frame_duration")]
+    pub frame_duration: f32,
+    #[serde(default)]
+    pub playback: Playback,
+    /// Clip to play after a `Playback::Once` clip finishes, instead of stopping.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+fn default_frame_duration() -> f32 {
+    FRAME_DURATION
+}
+
+impl Default for Clip {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl Clip {
+    /// Looping clip at the default speed.
+    pub fn new(first: usize, last: usize) -> Self {
+        Self {
+            first,
+            last,
+            frame_duration: FRAME_DURATION,
+            playback: Playback::Loop,
+            next: None,
+        }
+    }
+
+    pub fn frames(&self) -> RangeInclusive<usize> {
+        self.first..=self.last
+    }
+}
+
+impl SpriteManifest {
+    pub fn load(path: &Path) -> Result<Self, AssetError> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_ron(contents: &str) -> Result<Self, AssetError> {
+        let manifest: Self = ron::from_str(contents)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    pub fn validate(&self) -> Result<(), AssetError> {
+        let mut problems = Vec::new();
+
+        for (name, sheet) in &self.sheets {
+            let (w, h) = sheet.tile_size;
+            if w <= 0.0 || h <= 0.0 {
+                problems.push(format!("{}: tile_size must be above 0", name));
+            }
+            if sheet.columns == 0 || sheet.rows == 0 {
+                problems.push(format!("{}: needs at least one column and row", name));
+            }
+
+            for (clip_name, clip) in &sheet.clips {
+                if clip.first > clip.last {
+                    problems.push(format!("{}.{}: first is after last", name, clip_name));
+                }
+                if clip.frame_duration <= 0.0 {
+                    problems.push(format!(
+                        "{}.{}: frame_duration must be above 0",
+                        name, clip_name
+                    ));
+                }
+                if let Some(next) = &clip.next {
+                    if clip.playback != Playback::Once {
+                        problems.push(format!(
+                            "{}.{}: only Once clips can have a next clip",
+                            name, clip_name
+                        ));
+                    }
+                    if !sheet.clips.contains_key(next) {
+                        problems.push(format!("{}.{}: no {} clip", name, clip_name, next));
+                    }
+                }
+                if clip.last >= sheet.columns * sheet.rows {
+                    problems.push(format!(
+                        "{}.{}: frame {} is outside the {} frame sheet",
+                        name,
+                        clip_name,
+                        clip.last,
+                        sheet.columns * sheet.rows
+                    ));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AssetError::Invalid(problems))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SpriteManifestLoader;
+
+impl AssetLoader for SpriteManifestLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let manifest = SpriteManifest::from_ron(std::str::from_utf8(bytes)?)?;
+            load_context.set_default_asset(LoadedAsset::new(manifest));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["manifest.ron"]
+    }
+}
+
+/// A sprite sheet ready to use.
+#[derive(Clone, Debug, Default)]
+pub struct Sheet {
+    pub atlas: Handle<TextureAtlas>,
+    pub tile_size: Vec2,
+    /// Shared with the `Animator` of every sprite using the sheet.
+    pub clips: Arc<BTreeMap<String, Clip>>,
+}
+
+impl Sheet {
+    /// Frames of the named clip, or just the first frame if there is no such clip.
+    pub fn clip(&self, name: &str) -> RangeInclusive<usize> {
+        self.clips.get(name).map_or(0..=0, Clip::frames)
+    }
+
+    /// Animator starting on the named clip.
+    pub fn animator(&self, clip: &str) -> Animator {
+        Animator::new(self.clips.clone(), clip)
+    }
+}
+
+/// Placeholder handles by default, for running without any assets.
+#[derive(Default)]
+pub struct GameTextures {
+    pub player: Sheet,
+    pub player_fire: Sheet,
+    pub enemy_fire: Sheet,
+    pub explosion: Sheet,
+    /// Sheets for the `EnemyTypes`, by name.
+    pub enemies: HashMap<String, Sheet>,
+}
+
+impl GameTextures {
+    /// Check the manifest has everything the game needs, making an atlas for each sheet.
+    pub fn new(
+        manifest: &SpriteManifest,
+        mut make_atlas: impl FnMut(&SheetManifest) -> Handle<TextureAtlas>,
+    ) -> Result<Self, AssetError> {
+        let mut problems = Vec::new();
+        let mut sheets = HashMap::new();
+
+        for (name, clips) in REQUIRED_CLIPS {
+            let sheet = match manifest.sheets.get(*name) {
+                Some(sheet) => sheet,
+                None => {
+                    problems.push(format!("missing the {} sheet", name));
+                    continue;
+                }
+            };
+            for clip in clips
+                .iter()
+                .filter(|clip| !sheet.clips.contains_key(**clip))
+            {
+                problems.push(format!("{}: missing the {} clip", name, clip));
+            }
+
+            let ready = Sheet {
+                atlas: make_atlas(sheet),
+                tile_size: sheet.tile_size.into(),
+                clips: Arc::new(sheet.clips.clone()),
+            };
+            sheets.insert(*name, ready);
+        }
+
+        if !problems.is_empty() {
+            return Err(AssetError::Invalid(problems));
+        }
+
+        let mut take = |name| sheets.remove(name).unwrap_or_default();
+        Ok(Self {
+            player: take("player"),
+            player_fire: take("player_fire"),
+            enemy_fire: take("enemy_fire"),
+            explosion: take("explosion"),
+            enemies: sheets
+                .into_iter()
+                .map(|(name, sheet)| (name.to_string(), sheet))
+                .collect(),
+        })
+    }
+
+    /// The named enemy sheet, or a placeholder if there is no such sheet.
+    pub fn enemy(&self, name: &str) -> Sheet {
+        self.enemies.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Real sheet sizes and clips but placeholder images, for running headless.
+    pub fn placeholder() -> Result<Self, AssetError> {
+        let manifest = SpriteManifest::load(&asset_file(SPRITES_ASSET))?;
+        Self::new(&manifest, |_| Handle::default())
+    }
+}
+
+struct SpriteManifestHandle(Handle<SpriteManifest>);
+
+fn sheets_load_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SpriteManifestHandle(asset_server.load(SPRITES_ASSET)));
+}
+
+fn sheets_ready_system(
+    mut commands: Commands,
+    handle: Option<Res<SpriteManifestHandle>>,
+    manifests: Res<Assets<SpriteManifest>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    let handle = match handle {
+        Some(handle) => handle,
+        None => return,
+    };
+    let manifest = match manifests.get(&handle.0) {
+        Some(manifest) => manifest,
+        // The asset server has already logged why.
+        None if asset_server.get_load_state(&handle.0) == LoadState::Failed => {
+            commands.remove_resource::<SpriteManifestHandle>();
+            commands.insert_resource(LoadingFailed(format!("Couldn't load {}", SPRITES_ASSET)));
+            return;
+        }
+        None => return,
+    };
+    commands.remove_resource::<SpriteManifestHandle>();
+
+    let game_textures = GameTextures::new(manifest, |sheet| {
+        texture_atlases.add(TextureAtlas::from_grid(
+            asset_server.load(&sheet.path),
+            sheet.tile_size.into(),
+            sheet.columns,
+            sheet.rows,
+        ))
+    });
+    match game_textures {
+        Ok(game_textures) => {
+            commands.insert_resource(game_textures);
+            if let Err(err) = state.set(GameState::Title) {
+                warn!("Failed to show the title: {:?}", err);
+            }
+        }
+        Err(err) => {
+            error!("Invalid {}: {}", SPRITES_ASSET, err);
+            commands.insert_resource(LoadingFailed(format!("Invalid {}", SPRITES_ASSET)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_manifest_has_everything() {
+        let manifest =
+            SpriteManifest::from_ron(include_str!("../assets/sprites.manifest.ron")).unwrap();
+        let textures = GameTextures::new(&manifest, |_| Handle::default()).unwrap();
+        assert_eq!(textures.player.tile_size, Vec2::new(140.0, 168.0));
+        assert_eq!(textures.player.clip("run"), 14..=21);
+        assert_eq!(textures.explosion.clip("explode"), 0..=9);
+        assert_eq!(textures.explosion.clips["explode"].playback, Playback::Once);
+    }
+
+    #[test]
+    fn reports_bad_sheets() {
+        let err = SpriteManifest::from_ron(
+            r#"(sheets: {
+                "a": (path: "a.png", tile_size: (0, 10), columns: 2, rows: 1),
+                "b": (path: "b.png", tile_size: (10, 10), columns: 2, rows: 1, clips: {
+                    "run": (first: 1, last: 2),
+                    "jump": (first: 0, last: 1, frame_duration: 0, next: Some("fall")),
+                }),
+            })"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a: tile_size must be above 0; \
+            b.jump: frame_duration must be above 0; \
+            b.jump: only Once clips can have a next clip; \
+            b.jump: no fall clip; \
+            b.run: frame 2 is outside the 2 frame sheet"
+        );
+
+        let manifest = SpriteManifest::from_ron(
+            r#"(sheets: {
+                "player": (path: "p.png", tile_size: (10, 10), columns: 1, rows: 1),
+            })"#,
+        )
+        .unwrap();
+        let err = GameTextures::new(&manifest, |_| Handle::default())
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with(
+            "player: missing the idle clip; player: missing the run clip; player: missing the throw clip"
+        ));
+    }
+}
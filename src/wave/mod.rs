@@ -0,0 +1,343 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig,
+    enemy::{EnemyTypes, Movement, SquadShape},
+    rng::GameRng,
+    simulation::{ticks, SimulationClock, SimulationStage},
+    EnemyCount, GameState,
+};
+
+pub use self::script::{ScriptedWave, Side, SpawnEntry, WaveScript, WaveScriptPlugin, WAVES_ASSET};
+
+mod script;
+
+/// Runs the game as a series of waves, each a set number of enemies with a breather before it.
+/// The waves come from the `WaveScript` for as long as it has any, and are made up after that.
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WaveState::default())
+            .init_resource::<WaveScript>()
+            .add_system_to_stage(SimulationStage, wave_system.label(WaveLabel))
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(wave_reset_system));
+    }
+}
+
+/// Label for the system moving the waves along, spawning runs after it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub struct WaveLabel;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WavePhase {
+    /// Waiting for the wave to start, with its banner up.
+    Breather,
+    /// Sending out the wave's enemies, until they are all gone.
+    Fighting,
+}
+
+/// An enemy waiting to be sent out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spawn {
+    /// Name in the `EnemyTypes`.
+    pub enemy: &'static str,
+    /// Instead of the enemy type's own movement.
+    pub movement: Option<Movement>,
+    pub side: Side,
+    /// Gives it a [`FireRate`](crate::components::FireRate).
+    pub fire_rate: Option<f64>,
+    /// Enemies in the same group share a squad. The rest fill up squads of
+    /// `formation_members_max`.
+    pub group: Option<u32>,
+    /// Shape of the squad it starts, picked at random if not given.
+    pub squad: Option<SquadShape>,
+    /// Tick it can come out on.
+    pub tick: u64,
+}
+
+impl Spawn {
+    fn new(enemy: &'static str, tick: u64) -> Self {
+        Self {
+            enemy,
+            movement: None,
+            side: Side::Random,
+            fire_rate: None,
+            group: None,
+            squad: None,
+            tick,
+        }
+    }
+}
+
+/// The wave being played. Wave 0 is before the game starts.
+#[derive(Debug)]
+pub struct WaveState {
+    pub number: u32,
+    pub phase: WavePhase,
+    /// Enemies still to spawn this wave, in the order they come out.
+    pub to_spawn: VecDeque<Spawn>,
+    /// Tick the breather ends on.
+    breather_end: u64,
+    /// Group of the next scripted spawn entry.
+    next_group: u32,
+}
+
+impl Default for WaveState {
+    fn default() -> Self {
+        Self {
+            number: 0,
+            phase: WavePhase::Fighting,
+            to_spawn: VecDeque::new(),
+            breather_end: 0,
+            next_group: 0,
+        }
+    }
+}
+
+impl WaveState {
+    /// Move on to the next wave, following the script or else picking its enemies, and take
+    /// a breather. Every `boss_every` waves the script doesn't have is a boss on its own.
+    pub fn next_wave(
+        &mut self,
+        config: &GameConfig,
+        enemy_types: &EnemyTypes,
+        script: &WaveScript,
+        tick: u64,
+        rng: &mut GameRng,
+    ) {
+        self.number += 1;
+        self.phase = WavePhase::Breather;
+        self.breather_end = tick + ticks(config.wave_breather);
+
+        self.to_spawn = match script.waves.get(self.number as usize - 1) {
+            Some(scripted) => self.scripted(scripted, enemy_types),
+            None => match self.boss(config, enemy_types, rng) {
+                Some(boss) => VecDeque::from([Spawn::new(boss, 0)]),
+                None => {
+                    let size = config.wave_enemies + config.wave_growth * (self.number - 1);
+                    (0..size)
+                        .map(|_| Spawn::new(enemy_types.choose(self.number, rng).name, 0))
+                        .collect()
+                }
+            },
+        };
+    }
+
+    /// The boss to fight this wave, if it's a boss wave.
+    fn boss(
+        &self,
+        config: &GameConfig,
+        enemy_types: &EnemyTypes,
+        rng: &mut GameRng,
+    ) -> Option<&'static str> {
+        if config.boss_every == 0 || !self.number.is_multiple_of(config.boss_every) {
+            return None;
+        }
+        enemy_types
+            .choose_boss(self.number, rng)
+            .map(|boss| boss.name)
+    }
+
+    /// Every enemy of a scripted wave, ordered by when they can come out.
+    fn scripted(&mut self, scripted: &ScriptedWave, enemy_types: &EnemyTypes) -> VecDeque<Spawn> {
+        let mut spawns = Vec::new();
+        for entry in &scripted.spawns {
+            // The script has been checked, but skip anything unknown rather than panic.
+            let enemy = match enemy_types.get(&entry.enemy) {
+                Some(enemy_type) => enemy_type.name,
+                None => continue,
+            };
+            let spawn = Spawn {
+                movement: entry.formation.as_deref().and_then(Movement::from_name),
+                side: entry.side,
+                fire_rate: entry.fire_rate,
+                group: Some(self.next_group),
+                squad: entry.squad,
+                ..Spawn::new(enemy, self.breather_end + ticks(entry.delay))
+            };
+            spawns.extend(std::iter::repeat_n(spawn, entry.count as usize));
+            self.next_group += 1;
+        }
+
+        // Stable, so groups starting together keep the script's order.
+        spawns.sort_by_key(|spawn| spawn.tick);
+        spawns.into()
+    }
+
+    /// Multiplier for the speed and fire rate of enemies, going up each wave.
+    pub fn difficulty(&self, config: &GameConfig) -> f32 {
+        1.0 + config.wave_escalation * self.number.saturating_sub(1) as f32
+    }
+
+    /// Enemies allowed out at once, one more every other wave.
+    pub fn enemy_max(&self, config: &GameConfig) -> u32 {
+        config.enemy_max + self.number.saturating_sub(1) / 2
+    }
+
+    /// The next enemy to send out, if it's time for one.
+    pub fn spawn(
+        &mut self,
+        config: &GameConfig,
+        enemy_count: &EnemyCount,
+        tick: u64,
+    ) -> Option<Spawn> {
+        let due = self
+            .to_spawn
+            .front()
+            .is_some_and(|spawn| spawn.tick <= tick);
+        if due && self.phase == WavePhase::Fighting && enemy_count.0 < self.enemy_max(config) {
+            self.to_spawn.pop_front()
+        } else {
+            None
+        }
+    }
+}
+
+fn wave_reset_system(mut wave: ResMut<WaveState>) {
+    *wave = WaveState::default();
+}
+
+fn wave_system(
+    mut wave: ResMut<WaveState>,
+    config: Res<GameConfig>,
+    enemy_types: Res<EnemyTypes>,
+    script: Res<WaveScript>,
+    enemy_count: Res<EnemyCount>,
+    clock: Res<SimulationClock>,
+    mut rng: ResMut<GameRng>,
+) {
+    match wave.phase {
+        WavePhase::Breather if clock.tick >= wave.breather_end => {
+            wave.phase = WavePhase::Fighting;
+        }
+        WavePhase::Fighting if wave.to_spawn.is_empty() && enemy_count.0 == 0 => {
+            wave.next_wave(&config, &enemy_types, &script, clock.tick, &mut rng);
+            info!("Wave {}: {} enemies", wave.number, wave.to_spawn.len());
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemy::PatternKind;
+
+    #[test]
+    fn waves_grow_and_get_harder() {
+        let config = GameConfig::default();
+        let enemy_types = EnemyTypes::default();
+        let script = WaveScript::default();
+        let mut rng = GameRng::new(1);
+        let mut wave = WaveState::default();
+
+        wave.next_wave(&config, &enemy_types, &script, 100, &mut rng);
+        assert_eq!(wave.number, 1);
+        assert_eq!(wave.to_spawn.len() as u32, config.wave_enemies);
+        assert!(wave.to_spawn.iter().all(|spawn| spawn.enemy == "ninja_cat"));
+        assert_eq!(wave.difficulty(&config), 1.0);
+        assert_eq!(wave.spawn(&config, &EnemyCount(0), 100), None);
+
+        wave.phase = WavePhase::Fighting;
+        let spawn = wave.spawn(&config, &EnemyCount(0), 100);
+        assert_eq!(spawn.map(|spawn| spawn.enemy), Some("ninja_cat"));
+        assert_eq!(
+            wave.spawn(&config, &EnemyCount(config.enemy_max), 100),
+            None
+        );
+
+        wave.next_wave(&config, &enemy_types, &script, 200, &mut rng);
+        wave.next_wave(&config, &enemy_types, &script, 300, &mut rng);
+        assert_eq!(
+            wave.to_spawn.len() as u32,
+            config.wave_enemies + 2 * config.wave_growth
+        );
+        assert!(wave.difficulty(&config) > 1.0);
+        assert_eq!(wave.enemy_max(&config), config.enemy_max + 1);
+    }
+
+    #[test]
+    fn follows_the_script() {
+        let config = GameConfig::default();
+        let enemy_types = EnemyTypes::default();
+        let script = WaveScript::from_ron(
+            r#"(waves: [
+                (spawns: [
+                    (enemy: "star", count: 2, delay: 1, fire_rate: Some(2)),
+                    (enemy: "penguin", formation: Some("dive_bomb"), side: Left),
+                ]),
+            ])"#,
+            &enemy_types,
+        )
+        .unwrap();
+        let mut rng = GameRng::new(1);
+        let mut wave = WaveState::default();
+
+        wave.next_wave(&config, &enemy_types, &script, 0, &mut rng);
+        let breather = ticks(config.wave_breather);
+        let spawns: Vec<_> = wave
+            .to_spawn
+            .iter()
+            .map(|spawn| (spawn.enemy, spawn.group, spawn.tick))
+            .collect();
+        assert_eq!(
+            spawns,
+            [
+                ("penguin", Some(1), breather),
+                ("star", Some(0), breather + 60),
+                ("star", Some(0), breather + 60),
+            ]
+        );
+        assert_eq!(
+            wave.to_spawn[0].movement,
+            Some(Movement::Formation(PatternKind::DiveBomb))
+        );
+        assert_eq!(wave.to_spawn[0].side, Side::Left);
+        assert_eq!(wave.to_spawn[1].fire_rate, Some(2.0));
+
+        // Stars wait for their delay.
+        wave.phase = WavePhase::Fighting;
+        let mut spawned = |tick| {
+            wave.spawn(&config, &EnemyCount(0), tick)
+                .map(|spawn| spawn.enemy)
+        };
+        assert_eq!(spawned(breather), Some("penguin"));
+        assert_eq!(spawned(breather + 30), None);
+        assert_eq!(spawned(breather + 60), Some("star"));
+
+        // Past the end of the script, the waves are made up.
+        wave.next_wave(&config, &enemy_types, &script, 1000, &mut rng);
+        assert_eq!(
+            wave.to_spawn.len() as u32,
+            config.wave_enemies + config.wave_growth
+        );
+        assert!(wave.to_spawn.iter().all(|spawn| spawn.group.is_none()));
+    }
+
+    #[test]
+    fn bosses_come_every_few_waves() {
+        let config = GameConfig {
+            boss_every: 2,
+            ..Default::default()
+        };
+        let enemy_types = EnemyTypes::default();
+        let script = WaveScript::default();
+        let mut rng = GameRng::new(1);
+        let mut wave = WaveState::default();
+        let mut enemies = |wave: &mut WaveState| {
+            wave.next_wave(&config, &enemy_types, &script, 0, &mut rng);
+            wave.to_spawn
+                .iter()
+                .map(|spawn| spawn.enemy)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(!enemies(&mut wave).contains(&"ninja_cat_boss"));
+        assert_eq!(enemies(&mut wave), ["ninja_cat_boss"]);
+        assert!(!enemies(&mut wave).contains(&"ninja_cat_boss"));
+        assert_eq!(enemies(&mut wave), ["ninja_cat_boss"]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::{fs, io, path::Path};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    enemy::{EnemyTypes, Movement, SquadShape},
+    error::AssetError,
+};
+
+/// Path of the wave script in the assets folder.
+pub const WAVES_ASSET: &str = "game.waves.ron";
+
+/// Picks up changes to the wave script while the game runs, keeping the old one if a change
+/// is invalid. Needs the `AssetPlugin`, and the starting script from [`WaveScript::load`].
+pub struct WaveScriptPlugin;
+
+impl Plugin for WaveScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<WaveScript>()
+            .init_asset_loader::<WaveScriptLoader>()
+            .add_startup_system(wave_script_load_system)
+            .add_system(wave_script_reload_system);
+    }
+}
+
+/// Waves made by hand, played in order before the game goes back to making up its own.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "9d3c2b71-4e5a-4f86-b0c7-2a1e8f6d5c34"]
+#[serde(deny_unknown_fields)]
+pub struct WaveScript {
+    pub waves: Vec<ScriptedWave>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptedWave {
+    pub spawns: Vec<SpawnEntry>,
+}
+
+/// A group of enemies of one type, flying together as a squad.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpawnEntry {
+    /// Name in the `EnemyTypes`.
+    pub enemy: String,
+    /// How they get around, `walk` or the name of a formation pattern like `orbit` or
+    /// `dive_bomb`. The enemy type's own movement if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formation: Option<String>,
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// How they line up, picked at random if not given. Walkers don't line up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub squad: Option<SquadShape>,
+    #[serde(default)]
+    pub side: Side,
+    /// Seconds into the wave before they start coming out. Whole seconds only, since enemies
+    /// come out one a second.
+    #[serde(default)]
+    pub delay: f64,
+    /// [`FireRate`](crate::components::FireRate) for each of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fire_rate: Option<f64>,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// Side of the screen enemies come in from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Left,
+    Right,
+    /// Either side, picked for each formation.
+    #[default]
+    Random,
+}
+
+impl WaveScript {
+    /// Load and check the script straight away, or have no scripted waves if there is no
+    /// such file.
+    pub fn load(path: &Path, enemy_types: &EnemyTypes) -> Result<Self, AssetError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::from_ron(&contents, enemy_types),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn from_ron(contents: &str, enemy_types: &EnemyTypes) -> Result<Self, AssetError> {
+        let script: Self = ron::from_str(contents)?;
+        script.validate(enemy_types)?;
+        Ok(script)
+    }
+
+    pub fn validate(&self, enemy_types: &EnemyTypes) -> Result<(), AssetError> {
+        let mut problems = Vec::new();
+
+        for (wave, scripted) in self.waves.iter().enumerate() {
+            let wave = wave + 1;
+            if scripted.spawns.is_empty() {
+                problems.push(format!("wave {}: has no spawns", wave));
+            }
+
+            for (index, entry) in scripted.spawns.iter().enumerate() {
+                let mut check = |ok: bool, problem: &str| {
+                    if !ok {
+                        problems.push(format!("wave {}, spawn {}: {}", wave, index + 1, problem));
+                    }
+                };
+
+                check(
+                    enemy_types.get(&entry.enemy).is_some(),
+                    &format!("no {} enemy type", entry.enemy),
+                );
+                if let Some(formation) = &entry.formation {
+                    check(
+                        Movement::from_name(formation).is_some(),
+                        &format!("no {} formation", formation),
+                    );
+                }
+                check(entry.count > 0, "count must be at least 1");
+                check(entry.delay >= 0.0, "delay can't be negative");
+                check(entry.delay.fract() == 0.0, "delay must be whole seconds");
+                check(
+                    entry.fire_rate.is_none_or(|fire_rate| fire_rate >= 0.0),
+                    "fire_rate can't be negative",
+                );
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(AssetError::Invalid(problems))
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            // The enemy types are fixed, so there's no need to wait for the resource.
+            let script = WaveScript::from_ron(std::str::from_utf8(bytes)?, &EnemyTypes::default())?;
+            load_context.set_default_asset(LoadedAsset::new(script));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+struct WaveScriptHandle(Handle<WaveScript>);
+
+fn wave_script_load_system(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WaveScriptHandle(asset_server.load(WAVES_ASSET)));
+}
+
+fn wave_script_reload_system(
+    mut events: EventReader<AssetEvent<WaveScript>>,
+    handle: Res<WaveScriptHandle>,
+    scripts: Res<Assets<WaveScript>>,
+    mut script: ResMut<WaveScript>,
+) {
+    for event in events.iter() {
+        let changed = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        if *changed != handle.0 {
+            continue;
+        }
+
+        if let Some(new_script) = scripts.get(changed) {
+            info!(
+                "Using {} scripted waves from {}",
+                new_script.waves.len(),
+                WAVES_ASSET
+            );
+            *script = new_script.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_script_is_valid() {
+        let script = WaveScript::from_ron(
+            include_str!("../../assets/game.waves.ron"),
+            &EnemyTypes::default(),
+        )
+        .unwrap();
+        assert!(!script.waves.is_empty());
+    }
+
+    #[test]
+    fn reports_bad_spawns() {
+        let err = WaveScript::from_ron(
+            r#"(waves: [
+                (spawns: [
+                    (enemy: "ninja_cat", formation: Some("orbit"), count: 2, side: Left),
+                    (enemy: "dragon", formation: Some("loop"), count: 0, delay: 0.3, fire_rate: Some(-1)),
+                ]),
+                (spawns: []),
+            ])"#,
+            &EnemyTypes::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "wave 1, spawn 2: no dragon enemy type; \
+            wave 1, spawn 2: no loop formation; \
+            wave 1, spawn 2: count must be at least 1; \
+            wave 1, spawn 2: delay must be whole seconds; \
+            wave 1, spawn 2: fire_rate can't be negative; \
+            wave 2: has no spawns"
+        );
+    }
+}
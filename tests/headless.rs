@@ -0,0 +1,347 @@
+use bevy::prelude::*;
+use monkey_fire::{
+    collision::{CollisionLayer, CollisionLayers},
+    components::{Damage, Dying, Enemy, ExplosionToSpawn, Fire, Health, Hurt, Player, SpriteSize},
+    config::GameConfig,
+    enemy::{Boss, EnemyTypes, FirePattern, Formation, Squad, SquadMember, Walk},
+    headless::{run_ticks, start_game, HeadlessPlugin, HEADLESS_WIN_SIZE},
+    input::PlayerInput,
+    replay::{Replay, ReplayPlayback},
+    rng::GameRng,
+    simulation::ticks,
+    wave::{WavePhase, WaveScript, WaveState},
+    EnemyCount, GamePlugin, GameState, PlayerState, Scoreboard, BASE_SPEED, BOSS_EXPLOSIONS,
+    DYING_DURATION, ENEMY_MAX, HURT_DURATION, PLAYER_LIVES, PLAYER_RESPAWN_DELAY, TIME_STEP,
+    WAVE_BREATHER, WAVE_ENEMIES,
+};
+
+/// Ticks until the player has spawned.
+const SPAWN_TICKS: u64 = 30;
+/// Ticks until the player can be hit.
+const INVULNERABLE_TICKS: u64 = 150;
+/// Ticks of the breather before the first wave.
+const BREATHER_TICKS: u64 = (WAVE_BREATHER / TIME_STEP as f64) as u64;
+
+fn new_app(seed: u64) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugin(HeadlessPlugin)
+        .insert_resource(GameRng::new(seed))
+        .add_plugin(GamePlugin);
+    start_game(&mut app);
+    app
+}
+
+fn play(app: &mut App, inputs: &[PlayerInput]) {
+    app.insert_resource(ReplayPlayback::new(&Replay::new(0, inputs)));
+}
+
+fn entities<T: Component>(app: &mut App) -> Vec<(Entity, Vec3)> {
+    app.world
+        .query_filtered::<(Entity, &Transform), With<T>>()
+        .iter(&app.world)
+        .map(|(entity, tf)| (entity, tf.translation))
+        .collect()
+}
+
+fn player_position(app: &mut App) -> Vec3 {
+    let players = entities::<Player>(app);
+    assert_eq!(players.len(), 1);
+    players[0].1
+}
+
+fn state(app: &App) -> GameState {
+    *app.world.resource::<State<GameState>>().current()
+}
+
+/// Spawn a bare projectile at `position` on the given layer.
+fn spawn_fire(app: &mut App, position: Vec3, layers: CollisionLayers) -> Entity {
+    app.world
+        .spawn()
+        .insert(Fire)
+        .insert(Transform::from_translation(position))
+        .insert(SpriteSize(Vec2::new(20.0, 20.0)))
+        .insert(layers)
+        .id()
+}
+
+/// Hit whatever enemy is at `position` with player fire doing `damage`, and let it land.
+fn hit(app: &mut App, position: Vec3, damage: u32) {
+    let fire = spawn_fire(
+        app,
+        position,
+        CollisionLayers::new(CollisionLayer::PlayerFire, &[CollisionLayer::Enemy]),
+    );
+    app.world.entity_mut(fire).insert(Damage(damage));
+    run_ticks(app, 1);
+}
+
+/// Like `run_ticks`, but despawn enemies as they appear so only our own fire hits the
+/// player.
+fn run_ticks_without_enemies(app: &mut App, ticks: u64) {
+    for _ in 0..ticks {
+        run_ticks(app, 1);
+        for (entity, _) in entities::<Enemy>(app) {
+            app.world.despawn(entity);
+        }
+        app.world.resource_mut::<EnemyCount>().0 = 0;
+    }
+}
+
+#[test]
+fn spawns_player_and_enemies() {
+    let mut app = new_app(1);
+    run_ticks(&mut app, SPAWN_TICKS);
+
+    let position = player_position(&mut app);
+    assert_eq!(position.x, 0.0);
+    assert!(position.y < -HEADLESS_WIN_SIZE.height / 2.0 + 100.0);
+
+    run_ticks(&mut app, BREATHER_TICKS + 60 * ENEMY_MAX as u64);
+    assert_eq!(app.world.resource::<EnemyCount>().0, ENEMY_MAX);
+    assert_eq!(entities::<Enemy>(&mut app).len(), ENEMY_MAX as usize);
+}
+
+#[test]
+fn clearing_a_wave_starts_the_next() {
+    let mut app = new_app(1);
+    run_ticks(&mut app, 1);
+    let wave = app.world.resource::<WaveState>();
+    assert_eq!((wave.number, wave.phase), (1, WavePhase::Breather));
+
+    run_ticks_without_enemies(&mut app, BREATHER_TICKS + 60 * WAVE_ENEMIES as u64 + 1);
+    let wave = app.world.resource::<WaveState>();
+    assert_eq!((wave.number, wave.phase), (2, WavePhase::Breather));
+}
+
+#[test]
+fn player_follows_input() {
+    let mut app = new_app(1);
+    let left = PlayerInput {
+        left: true,
+        ..Default::default()
+    };
+    let mut inputs = vec![PlayerInput::default(); SPAWN_TICKS as usize + 10];
+    inputs.extend(vec![left; 60]);
+    play(&mut app, &inputs);
+
+    run_ticks(&mut app, inputs.len() as u64 + 10);
+
+    let position = player_position(&mut app);
+    assert!((position.x + 60.0 * TIME_STEP * BASE_SPEED).abs() < 0.01);
+}
+
+#[test]
+fn hitting_an_enemy_scores() {
+    let mut app = new_app(1);
+    run_ticks(&mut app, BREATHER_TICKS + 70);
+
+    let enemies = entities::<Enemy>(&mut app);
+    assert_eq!(enemies.len(), 1);
+    spawn_fire(
+        &mut app,
+        enemies[0].1,
+        CollisionLayers::new(CollisionLayer::PlayerFire, &[CollisionLayer::Enemy]),
+    );
+    run_ticks(&mut app, 1);
+
+    assert_eq!(app.world.resource::<Scoreboard>().score, 1);
+    assert_eq!(app.world.resource::<EnemyCount>().0, 0);
+    assert!(entities::<Enemy>(&mut app).is_empty());
+    assert_eq!(
+        app.world
+            .query::<&ExplosionToSpawn>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn tough_enemies_take_several_hits() {
+    let mut app = new_app(1);
+    run_ticks(&mut app, BREATHER_TICKS + 70);
+
+    let (enemy, position) = entities::<Enemy>(&mut app)[0];
+    app.world.entity_mut(enemy).insert(Health(3));
+    hit(&mut app, position, 2);
+    assert_eq!(app.world.get::<Health>(enemy).unwrap().0, 1);
+    assert!(app.world.get::<Hurt>(enemy).is_some());
+    assert_eq!(app.world.resource::<Scoreboard>().score, 0);
+
+    // Only hurt for a while, however long the frames take.
+    run_ticks(&mut app, ticks(HURT_DURATION));
+    assert!(app.world.get::<Hurt>(enemy).is_none());
+    let (_, position) = entities::<Enemy>(&mut app)[0];
+
+    hit(&mut app, position, 2);
+    assert!(app.world.get_entity(enemy).is_none());
+    assert_eq!(app.world.resource::<Scoreboard>().score, 1);
+    assert_eq!(
+        app.world
+            .query::<&ExplosionToSpawn>()
+            .iter(&app.world)
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn bosses_change_tactics_as_they_lose_health() {
+    let mut app = new_app(1);
+    app.insert_resource(GameConfig {
+        boss_every: 1,
+        ..Default::default()
+    });
+    run_ticks(&mut app, BREATHER_TICKS + 70);
+
+    let (boss, position) = entities::<Boss>(&mut app)[0];
+    assert_eq!(app.world.get::<Health>(boss).unwrap().0, 40);
+    assert!(app.world.get::<Walk>(boss).is_some());
+    // Down to 23 of 40, into the second phase.
+    hit(&mut app, position, 17);
+    assert_eq!(app.world.get::<Boss>(boss).unwrap().phase, 1);
+    assert!(app.world.get::<Walk>(boss).is_none());
+    assert!(app.world.get::<Formation>(boss).is_some());
+    assert_eq!(
+        app.world.get::<FirePattern>(boss),
+        Some(&FirePattern::Spread(5))
+    );
+
+    let (_, position) = entities::<Boss>(&mut app)[0];
+    hit(&mut app, position, 23);
+    assert!(app.world.get_entity(boss).is_none());
+    assert_eq!(app.world.resource::<Scoreboard>().score, 20);
+    assert_eq!(
+        app.world
+            .query::<&ExplosionToSpawn>()
+            .iter(&app.world)
+            .count(),
+        1 + BOSS_EXPLOSIONS
+    );
+}
+
+#[test]
+fn squads_close_up_then_scatter() {
+    let mut app = new_app(1);
+    app.insert_resource(
+        WaveScript::from_ron(
+            r#"(waves: [(spawns: [(enemy: "ninja_cat", count: 4, squad: Some(Line))])])"#,
+            &EnemyTypes::default(),
+        )
+        .unwrap(),
+    );
+    app.insert_resource(GameConfig {
+        enemy_max: 4,
+        ..Default::default()
+    });
+    run_ticks(&mut app, BREATHER_TICKS + 4 * 60 + 120);
+
+    let members = |app: &mut App| {
+        let mut members: Vec<_> = app
+            .world
+            .query::<(Entity, &SquadMember, &Transform)>()
+            .iter(&app.world)
+            .map(|(entity, member, tf)| (member.slot, entity, member.squad, tf.translation))
+            .collect();
+        members.sort_by_key(|member| member.0);
+        members
+    };
+    let lose = |app: &mut App, entity| {
+        app.world.despawn(entity);
+        app.world.resource_mut::<EnemyCount>().0 -= 1;
+        run_ticks(app, 1);
+    };
+
+    // Side by side, following the one squad.
+    let squad = members(&mut app);
+    assert_eq!(squad.len(), 4);
+    assert!(squad.iter().all(|member| member.2 == squad[0].2));
+    for pair in squad.windows(2) {
+        assert!(pair[0].3.distance(pair[1].3) > 50.0);
+        assert!((pair[0].3.y - pair[1].3.y).abs() < 1.0);
+    }
+
+    // Losing one, the rest close up.
+    lose(&mut app, squad[1].1);
+    let closed_up = members(&mut app);
+    let slots: Vec<_> = closed_up
+        .iter()
+        .map(|member| (member.0, member.1))
+        .collect();
+    assert_eq!(slots, [(0, squad[0].1), (1, squad[2].1), (2, squad[3].1)]);
+    assert_eq!(app.world.get::<Squad>(squad[0].2).unwrap().members, 3);
+
+    // Down to the last one of four, it goes its own way.
+    lose(&mut app, squad[0].1);
+    lose(&mut app, squad[2].1);
+    assert!(members(&mut app).is_empty());
+    assert!(app.world.get_entity(squad[0].2).is_none());
+    assert!(app.world.get::<Formation>(squad[3].1).is_some());
+}
+
+#[test]
+fn getting_hit_loses_a_life_then_the_game() {
+    let mut app = new_app(1);
+    app.world.resource_mut::<Scoreboard>().score = 5;
+
+    // Invulnerable straight after spawning.
+    run_ticks_without_enemies(&mut app, SPAWN_TICKS + 1);
+    let position = player_position(&mut app);
+    let fire = spawn_fire(
+        &mut app,
+        position,
+        CollisionLayers::new(CollisionLayer::EnemyFire, &[CollisionLayer::Player]),
+    );
+    run_ticks_without_enemies(&mut app, 1);
+    assert_eq!(app.world.resource::<PlayerState>().lives, PLAYER_LIVES);
+    assert!(app.world.get_entity(fire).is_some());
+    app.world.despawn(fire);
+
+    let delay = (PLAYER_RESPAWN_DELAY / TIME_STEP as f64) as u64 + SPAWN_TICKS;
+    for lives in (0..PLAYER_LIVES).rev() {
+        run_ticks_without_enemies(&mut app, INVULNERABLE_TICKS);
+        let position = player_position(&mut app);
+        spawn_fire(
+            &mut app,
+            position,
+            CollisionLayers::new(CollisionLayer::EnemyFire, &[CollisionLayer::Player]),
+        );
+        run_ticks_without_enemies(&mut app, 1);
+
+        assert_eq!(app.world.resource::<PlayerState>().lives, lives);
+        assert!(entities::<Player>(&mut app).is_empty());
+        assert!(entities::<Dying>(&mut app)
+            .iter()
+            .any(|(_, body)| *body == position));
+        assert_eq!(state(&app), GameState::Playing);
+
+        // The body explodes on time, however long the frames take.
+        run_ticks_without_enemies(&mut app, ticks(DYING_DURATION));
+        assert!(entities::<Dying>(&mut app).is_empty());
+
+        run_ticks_without_enemies(&mut app, delay - ticks(DYING_DURATION));
+    }
+
+    assert_eq!(state(&app), GameState::GameOver);
+    assert_eq!(app.world.resource::<Scoreboard>().score, 5 - PLAYER_LIVES);
+}
+
+#[test]
+fn same_seed_same_game() {
+    let run = |seed| {
+        let mut app = new_app(seed);
+        run_ticks(&mut app, 600);
+        let mut enemies: Vec<Vec3> = entities::<Enemy>(&mut app)
+            .into_iter()
+            .map(|(_, position)| position)
+            .collect();
+        enemies.sort_by(|a, b| a.x.total_cmp(&b.x));
+        enemies
+    };
+
+    let enemies = run(3);
+    assert_eq!(enemies.len(), ENEMY_MAX as usize);
+    assert_eq!(enemies, run(3));
+    assert_ne!(enemies, run(4));
+}